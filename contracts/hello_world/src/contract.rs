@@ -1,47 +1,131 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    entry_point, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    to_json_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, OverflowError,
+    OverflowOperation, Response, StdResult, Storage,
 };
+use cw2::{ensure_from_older_version, set_contract_version, CONTRACT};
+use cw_storage_plus::Bound;
 
 use crate::{
     contract::{
-        execute::{increment, reset},
-        query::get_count,
+        execute::{
+            accept_ownership, create_counter, decrement, increment, increment_by, reset,
+            transfer_ownership,
+        },
+        query::{
+            count_history, get_count, get_count_at_height, get_counter, get_owner, get_price,
+            list_counters,
+        },
     },
     error::ContractError,
-    msg::{ExecuteMsg, HelloResp, InstantiateMsg, QueryMsg},
-    state::{State, COUNT},
+    msg::{ExecuteMsg, HelloResp, InstantiateMsg, MigrateMsg, QueryMsg},
+    state::{save_count, Counter, COUNT, COUNTERS, LEGACY_COUNT, OWNER, PENDING_OWNER},
 };
 
-#[entry_point]
+const CONTRACT_NAME: &str = "crates.io:hello_world";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 100;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response> {
-    let state = State { count: msg.count };
-    COUNT.save(deps.storage, &state)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    save_count(deps.storage, env.block.height, &info.sender, msg.count)?;
+
+    let owner = match msg.owner {
+        Some(owner) => deps.api.addr_validate(&owner)?,
+        None => info.sender,
+    };
+    OWNER.save(deps.storage, &owner)?;
 
     Ok(Response::new()
         .add_attribute("method", "instantiate")
-        .add_attribute("owner", info.sender)
+        .add_attribute("owner", owner)
         .add_attribute("count", msg.count.to_string()))
 }
 
-#[entry_point]
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    // contracts without a version predate it and still have the legacy layout
+    let from_version = match CONTRACT.may_load(deps.storage)? {
+        Some(_) => {
+            ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?.to_string()
+        }
+        None => {
+            let owner = match msg.owner {
+                Some(owner) => Some(deps.api.addr_validate(&owner)?),
+                None => None,
+            };
+            migrate_legacy_layout(deps.storage, &env, owner)?;
+            set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+            "legacy".to_string()
+        }
+    };
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", from_version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+/// Moves the legacy `State` to `COUNT`, recorded as a change by the contract itself
+/// so the history starts at the migration. Contracts instantiated before the counter
+/// had an owner get `owner`.
+fn migrate_legacy_layout(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: Option<Addr>,
+) -> Result<(), ContractError> {
+    if !OWNER.exists(storage) {
+        OWNER.save(storage, &owner.ok_or(ContractError::OwnerRequired {})?)?;
+    }
+    let state = LEGACY_COUNT.load(storage)?;
+    save_count(
+        storage,
+        env.block.height,
+        &env.contract.address,
+        state.count,
+    )?;
+    LEGACY_COUNT.remove(storage);
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     use QueryMsg::*;
 
     match msg {
         HelloWorld {} => to_json_binary(&query::hello_world()?),
         GetCount {} => to_json_binary(&get_count(deps)?),
+        GetCountAtHeight { height } => to_json_binary(&get_count_at_height(deps, height)?),
+        CountHistory { start_after, limit } => {
+            to_json_binary(&count_history(deps, start_after, limit)?)
+        }
+        GetOwner {} => to_json_binary(&get_owner(deps)?),
+        GetPrice { pair } => to_json_binary(&get_price(deps, pair)?),
+        GetCounter { name } => to_json_binary(&get_counter(deps, name)?),
+        ListCounters { start_after, limit } => {
+            to_json_binary(&list_counters(deps, start_after, limit)?)
+        }
     }
 }
 
 mod query {
     use super::*;
 
-    use crate::msg::GetCountResp;
+    use crate::msg::{
+        CountChangeResp, CountHistoryResp, CounterResp, GetCountAtHeightResp, GetCountResp,
+        GetOwnerResp, GetPriceResp, ListCountersResp,
+    };
+    use crate::oracle::query_exchange_rate;
+    use crate::state::{COUNT_AT_HEIGHT, COUNT_HISTORY};
 
     pub fn hello_world() -> StdResult<HelloResp> {
         let response = HelloResp {
@@ -52,55 +136,805 @@ mod query {
     }
 
     pub fn get_count(deps: Deps) -> StdResult<GetCountResp> {
-        let state = COUNT.load(deps.storage)?;
+        let count = COUNT.load(deps.storage)?;
 
-        Ok(GetCountResp { count: state.count })
+        Ok(GetCountResp { count })
+    }
+
+    pub fn get_count_at_height(deps: Deps, height: u64) -> StdResult<GetCountAtHeightResp> {
+        // the last change at or before `height`
+        let count = COUNT_AT_HEIGHT
+            .range(
+                deps.storage,
+                None,
+                Some(Bound::inclusive(height)),
+                Order::Descending,
+            )
+            .next()
+            .transpose()?
+            .map(|(_, count)| count);
+
+        Ok(GetCountAtHeightResp { count })
+    }
+
+    pub fn count_history(
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<CountHistoryResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let changes = COUNT_HISTORY
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let (id, change) = item?;
+                Ok(CountChangeResp {
+                    id,
+                    height: change.height,
+                    sender: change.sender,
+                    count: change.count,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(CountHistoryResp { changes })
+    }
+
+    pub fn get_owner(deps: Deps) -> StdResult<GetOwnerResp> {
+        Ok(GetOwnerResp {
+            owner: OWNER.load(deps.storage)?,
+            pending_owner: PENDING_OWNER.may_load(deps.storage)?,
+        })
+    }
+
+    pub fn get_price(deps: Deps, pair: String) -> StdResult<GetPriceResp> {
+        let response = query_exchange_rate(&deps.querier, pair)?;
+
+        Ok(GetPriceResp {
+            exchange_rate: response.exchange_rate,
+        })
+    }
+
+    pub fn get_counter(deps: Deps, name: String) -> StdResult<CounterResp> {
+        let counter = COUNTERS.load(deps.storage, name.clone())?;
+
+        Ok(CounterResp {
+            name,
+            owner: counter.owner,
+            count: counter.count,
+        })
+    }
+
+    pub fn list_counters(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ListCountersResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let counters = COUNTERS
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let (name, counter) = item?;
+                Ok(CounterResp {
+                    name,
+                    owner: counter.owner,
+                    count: counter.count,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(ListCountersResp { counters })
     }
 }
 
-#[entry_point]
+#[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     use ExecuteMsg::*;
 
     match msg {
-        Increment {} => increment(deps, info),
-        Reset { count } => reset(deps, info, count),
+        Increment {} => increment(deps, env, info),
+        Reset { count } => reset(deps, env, info, count),
+        TransferOwnership { new_owner } => transfer_ownership(deps, info, new_owner),
+        AcceptOwnership {} => accept_ownership(deps, info),
+        CreateCounter { name, count } => create_counter(deps, info, name, count),
+        IncrementBy { name, delta } => increment_by(deps, info, name, delta),
+        Decrement { name } => decrement(deps, info, name),
     }
 }
 
 mod execute {
     use super::*;
-    pub fn increment(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
-        // Load the current state
-        let mut state = COUNT.load(deps.storage)?;
+
+    fn assert_owner(deps: &DepsMut, info: &MessageInfo) -> Result<(), ContractError> {
+        if OWNER.load(deps.storage)? != info.sender {
+            return Err(ContractError::Unauthorized {
+                sender: info.sender.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn increment(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        // Load the current count
+        let count = COUNT.load(deps.storage)?;
 
         // Increment the count by 1 (you can adjust this logic as needed)
-        state.count += 1;
+        let count = count
+            .checked_add(1)
+            .ok_or(OverflowError::new(OverflowOperation::Add))?;
 
-        // Save the updated state
-        COUNT.save(deps.storage, &state)?;
+        // Save the updated count
+        save_count(deps.storage, env.block.height, &info.sender, count)?;
 
         Ok(Response::new()
             .add_attribute("method", "increment")
             .add_attribute("owner", info.sender)
-            .add_attribute("count", state.count.to_string()))
+            .add_attribute("count", count.to_string()))
     }
 
-    pub fn reset(deps: DepsMut, info: MessageInfo, count: i32) -> Result<Response, ContractError> {
-        // Create a new state with the specified count
-        let state = State { count };
+    pub fn reset(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        count: i32,
+    ) -> Result<Response, ContractError> {
+        assert_owner(&deps, &info)?;
 
-        // Save the new state
-        COUNT.save(deps.storage, &state)?;
+        // Save the specified count
+        save_count(deps.storage, env.block.height, &info.sender, count)?;
 
         Ok(Response::new()
             .add_attribute("method", "reset")
             .add_attribute("caller", info.sender)
             .add_attribute("count", count.to_string()))
     }
+
+    pub fn transfer_ownership(
+        deps: DepsMut,
+        info: MessageInfo,
+        new_owner: String,
+    ) -> Result<Response, ContractError> {
+        assert_owner(&deps, &info)?;
+
+        // the current owner stays in charge until the new one accepts
+        let new_owner = deps.api.addr_validate(&new_owner)?;
+        PENDING_OWNER.save(deps.storage, &new_owner)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "transfer_ownership")
+            .add_attribute("pending_owner", new_owner))
+    }
+
+    pub fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+        if PENDING_OWNER.may_load(deps.storage)? != Some(info.sender.clone()) {
+            return Err(ContractError::Unauthorized {
+                sender: info.sender,
+            });
+        }
+        PENDING_OWNER.remove(deps.storage);
+        OWNER.save(deps.storage, &info.sender)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "accept_ownership")
+            .add_attribute("owner", info.sender))
+    }
+
+    pub fn create_counter(
+        deps: DepsMut,
+        info: MessageInfo,
+        name: String,
+        count: i32,
+    ) -> Result<Response, ContractError> {
+        if COUNTERS.has(deps.storage, name.clone()) {
+            return Err(ContractError::CounterExists { name });
+        }
+        let counter = Counter {
+            owner: info.sender,
+            count,
+        };
+        COUNTERS.save(deps.storage, name.clone(), &counter)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "create_counter")
+            .add_attribute("name", name)
+            .add_attribute("owner", counter.owner)
+            .add_attribute("count", count.to_string()))
+    }
+
+    /// Loads a named counter the sender owns
+    fn load_owned_counter(
+        deps: &DepsMut,
+        info: &MessageInfo,
+        name: &str,
+    ) -> Result<Counter, ContractError> {
+        let counter = COUNTERS
+            .may_load(deps.storage, name.to_string())?
+            .ok_or_else(|| ContractError::CounterNotFound {
+                name: name.to_string(),
+            })?;
+        if counter.owner != info.sender {
+            return Err(ContractError::Unauthorized {
+                sender: info.sender.clone(),
+            });
+        }
+        Ok(counter)
+    }
+
+    pub fn increment_by(
+        deps: DepsMut,
+        info: MessageInfo,
+        name: String,
+        delta: i32,
+    ) -> Result<Response, ContractError> {
+        let mut counter = load_owned_counter(&deps, &info, &name)?;
+
+        let operation = match delta < 0 {
+            true => OverflowOperation::Sub,
+            false => OverflowOperation::Add,
+        };
+        counter.count = counter
+            .count
+            .checked_add(delta)
+            .ok_or(OverflowError::new(operation))?;
+        COUNTERS.save(deps.storage, name.clone(), &counter)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "increment_by")
+            .add_attribute("name", name)
+            .add_attribute("count", counter.count.to_string()))
+    }
+
+    pub fn decrement(
+        deps: DepsMut,
+        info: MessageInfo,
+        name: String,
+    ) -> Result<Response, ContractError> {
+        let mut counter = load_owned_counter(&deps, &info, &name)?;
+
+        counter.count = counter
+            .count
+            .checked_sub(1)
+            .ok_or(OverflowError::new(OverflowOperation::Sub))?;
+        COUNTERS.save(deps.storage, name.clone(), &counter)?;
+
+        Ok(Response::new()
+            .add_attribute("method", "decrement")
+            .add_attribute("name", name)
+            .add_attribute("count", counter.count.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use super::*;
+    use crate::msg::{
+        CountHistoryResp, CounterResp, GetCountAtHeightResp, GetCountResp, GetOwnerResp,
+        GetPriceResp, ListCountersResp,
+    };
+    use crate::oracle::{ExchangeRateRequest, EXCHANGE_RATE_PATH};
+    use crate::state::State;
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{
+        from_json, ContractResult, Empty, OwnedDeps, Querier, QuerierResult, QueryRequest,
+        SystemError, SystemResult,
+    };
+
+    /// Stands in for the Nibiru oracle module, answering `ExchangeRate` stargate queries
+    struct OracleQuerier {
+        base: MockQuerier,
+        rates: Vec<(String, String)>,
+    }
+
+    impl Querier for OracleQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            #[allow(deprecated)]
+            match from_json(bin_request) {
+                Ok(QueryRequest::<Empty>::Stargate { path, data })
+                    if path == EXCHANGE_RATE_PATH =>
+                {
+                    let rate = self.rates.iter().find(|(pair, _)| {
+                        ExchangeRateRequest { pair: pair.clone() }.encode() == data
+                    });
+                    let response = match rate {
+                        Some((_, rate)) => ContractResult::Ok(
+                            to_json_binary(&serde_json::json!({
+                                "exchange_rate": rate,
+                                "block_timestamp_ms": "1700000000000",
+                                "block_height": "12345",
+                            }))
+                            .unwrap(),
+                        ),
+                        None => ContractResult::Err("pair not found".to_string()),
+                    };
+                    SystemResult::Ok(response)
+                }
+                Ok(QueryRequest::Stargate { path, .. }) => {
+                    SystemResult::Err(SystemError::UnsupportedRequest { kind: path })
+                }
+                _ => self.base.raw_query(bin_request),
+            }
+        }
+    }
+
+    fn oracle_dependencies(
+        rates: &[(&str, &str)],
+    ) -> OwnedDeps<MockStorage, MockApi, OracleQuerier> {
+        OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: OracleQuerier {
+                base: MockQuerier::default(),
+                rates: rates
+                    .iter()
+                    .map(|(pair, rate)| (pair.to_string(), rate.to_string()))
+                    .collect(),
+            },
+            custom_query_type: PhantomData,
+        }
+    }
+
+    fn setup(deps: DepsMut, owner: &str) {
+        let msg = InstantiateMsg {
+            count: 7,
+            owner: None,
+        };
+        instantiate(deps, mock_env(), mock_info(owner, &[]), msg).unwrap();
+    }
+
+    fn owner(deps: Deps) -> GetOwnerResp {
+        from_json(query(deps, mock_env(), QueryMsg::GetOwner {}).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn reset_is_owner_only() {
+        let mut deps = mock_dependencies();
+        let creator = deps.api.addr_make("creator");
+        let anyone = deps.api.addr_make("anyone");
+        setup(deps.as_mut(), creator.as_str());
+        assert_eq!(creator, owner(deps.as_ref()).owner);
+
+        let msg = ExecuteMsg::Reset { count: 0 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(anyone.as_str(), &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { sender } if sender == anyone));
+
+        // anyone can still increment
+        let increment = ExecuteMsg::Increment {};
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(anyone.as_str(), &[]),
+            increment,
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(creator.as_str(), &[]),
+            msg,
+        )
+        .unwrap();
+        let res: GetCountResp =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap()).unwrap();
+        assert_eq!(0, res.count);
+    }
+
+    #[test]
+    fn ownership_transfer() {
+        let mut deps = mock_dependencies();
+        let creator = deps.api.addr_make("creator");
+        let admin = deps.api.addr_make("admin");
+        let anyone = deps.api.addr_make("anyone");
+        setup(deps.as_mut(), creator.as_str());
+
+        let msg = ExecuteMsg::TransferOwnership {
+            new_owner: admin.to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(anyone.as_str(), &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(creator.as_str(), &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(Some(admin.clone()), owner(deps.as_ref()).pending_owner);
+
+        // only the proposed owner takes over
+        let accept = ExecuteMsg::AcceptOwnership {};
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(anyone.as_str(), &[]),
+            accept.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(admin.as_str(), &[]),
+            accept,
+        )
+        .unwrap();
+        let res = owner(deps.as_ref());
+        assert_eq!(admin, res.owner);
+        assert_eq!(None, res.pending_owner);
+
+        let msg = ExecuteMsg::Reset { count: 1 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(creator.as_str(), &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { .. }));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(admin.as_str(), &[]),
+            msg,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn get_price_queries_the_oracle() {
+        let deps = oracle_dependencies(&[("ubtc:unusd", "43210.500000000000000000")]);
+
+        let msg = QueryMsg::GetPrice {
+            pair: "ubtc:unusd".to_string(),
+        };
+        let res: GetPriceResp = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!("43210.500000000000000000", res.exchange_rate);
+
+        // the oracle has no price for unknown pairs
+        let msg = QueryMsg::GetPrice {
+            pair: "ueth:unusd".to_string(),
+        };
+        query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    }
+
+    #[test]
+    fn exchange_rate_request_encoding() {
+        let request = ExchangeRateRequest {
+            pair: "ubtc:unusd".to_string(),
+        };
+        let mut expected = vec![0x0a, 10];
+        expected.extend_from_slice(b"ubtc:unusd");
+        assert_eq!(expected, request.encode().to_vec());
+
+        // lengths from 128 bytes on take a second varint byte
+        let request = ExchangeRateRequest {
+            pair: "a".repeat(300),
+        };
+        assert_eq!([0x0a, 0xac, 0x02], request.encode()[..3]);
+    }
+
+    #[test]
+    fn named_counters() {
+        let mut deps = mock_dependencies();
+        let creator = deps.api.addr_make("creator");
+        let alice = deps.api.addr_make("alice");
+        let bob = deps.api.addr_make("bob");
+        setup(deps.as_mut(), creator.as_str());
+
+        for (sender, name) in [(&alice, "apples"), (&bob, "bananas"), (&alice, "cherries")] {
+            let msg = ExecuteMsg::CreateCounter {
+                name: name.to_string(),
+                count: 0,
+            };
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(sender.as_str(), &[]),
+                msg,
+            )
+            .unwrap();
+        }
+        let msg = ExecuteMsg::CreateCounter {
+            name: "apples".to_string(),
+            count: 5,
+        };
+        let err =
+            execute(deps.as_mut(), mock_env(), mock_info(bob.as_str(), &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::CounterExists { .. }));
+
+        let msg = ExecuteMsg::IncrementBy {
+            name: "apples".to_string(),
+            delta: 10,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice.as_str(), &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        // only the counter owner changes it
+        let err =
+            execute(deps.as_mut(), mock_env(), mock_info(bob.as_str(), &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized { sender } if sender == bob));
+        let msg = ExecuteMsg::Decrement {
+            name: "apples".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice.as_str(), &[]),
+            msg,
+        )
+        .unwrap();
+
+        let msg = QueryMsg::GetCounter {
+            name: "apples".to_string(),
+        };
+        let res: CounterResp = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(alice, res.owner);
+        assert_eq!(9, res.count);
+
+        let msg = ExecuteMsg::Decrement {
+            name: "dates".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice.as_str(), &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CounterNotFound { .. }));
+
+        let msg = QueryMsg::ListCounters {
+            start_after: None,
+            limit: Some(2),
+        };
+        let res: ListCountersResp =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let names: Vec<_> = res.counters.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(vec!["apples", "bananas"], names);
+        let msg = QueryMsg::ListCounters {
+            start_after: Some("bananas".to_string()),
+            limit: None,
+        };
+        let res: ListCountersResp =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(1, res.counters.len());
+        assert_eq!("cherries", res.counters[0].name);
+    }
+
+    #[test]
+    fn counters_do_not_overflow() {
+        let mut deps = mock_dependencies();
+        let alice = deps.api.addr_make("alice");
+        setup(deps.as_mut(), alice.as_str());
+
+        let msg = ExecuteMsg::CreateCounter {
+            name: "edge".to_string(),
+            count: i32::MAX - 1,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice.as_str(), &[]),
+            msg,
+        )
+        .unwrap();
+        let msg = ExecuteMsg::IncrementBy {
+            name: "edge".to_string(),
+            delta: 2,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice.as_str(), &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Overflow(_)));
+
+        let msg = ExecuteMsg::CreateCounter {
+            name: "floor".to_string(),
+            count: i32::MIN,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice.as_str(), &[]),
+            msg,
+        )
+        .unwrap();
+        let msg = ExecuteMsg::Decrement {
+            name: "floor".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice.as_str(), &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Overflow(_)));
+
+        // the contract counter is checked as well
+        let msg = ExecuteMsg::Reset { count: i32::MAX };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice.as_str(), &[]),
+            msg,
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice.as_str(), &[]),
+            ExecuteMsg::Increment {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Overflow(_)));
+    }
+
+    #[test]
+    fn instantiate_sets_version() {
+        let mut deps = mock_dependencies();
+        let creator = deps.api.addr_make("creator");
+        setup(deps.as_mut(), creator.as_str());
+
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(CONTRACT_NAME, version.contract);
+        assert_eq!(CONTRACT_VERSION, version.version);
+
+        // only hello_world can be upgraded, and never to an older version
+        let msg = MigrateMsg { owner: None };
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+        migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap_err();
+        set_contract_version(&mut deps.storage, "crates.io:other", "0.0.1").unwrap();
+        migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap_err();
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.0.1").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!("0.0.1", res.attributes[1].value);
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(CONTRACT_VERSION, version.version);
+    }
+
+    #[test]
+    fn migrate_legacy_count() {
+        let mut deps = mock_dependencies();
+        let admin = deps.api.addr_make("admin");
+        LEGACY_COUNT
+            .save(&mut deps.storage, &State { count: 42 })
+            .unwrap();
+
+        // the legacy contract had no owner to carry over
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None }).unwrap_err();
+        assert!(matches!(err, ContractError::OwnerRequired {}));
+
+        let msg = MigrateMsg {
+            owner: Some(admin.to_string()),
+        };
+        let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!("legacy", res.attributes[1].value);
+        assert!(!LEGACY_COUNT.exists(&deps.storage));
+        let res: GetCountResp =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap()).unwrap();
+        assert_eq!(42, res.count);
+        assert_eq!(admin, owner(deps.as_ref()).owner);
+        let msg = QueryMsg::CountHistory {
+            start_after: None,
+            limit: None,
+        };
+        let res: CountHistoryResp =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(1, res.changes.len());
+        assert_eq!(mock_env().block.height, res.changes[0].height);
+        assert_eq!(mock_env().contract.address, res.changes[0].sender);
+        assert_eq!(42, res.changes[0].count);
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(CONTRACT_VERSION, version.version);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(admin.as_str(), &[]),
+            ExecuteMsg::Increment {},
+        )
+        .unwrap();
+        let res: GetCountResp =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap()).unwrap();
+        assert_eq!(43, res.count);
+    }
+
+    #[test]
+    fn count_history() {
+        let mut deps = mock_dependencies();
+        let creator = deps.api.addr_make("creator");
+        let anyone = deps.api.addr_make("anyone");
+        let mut env = mock_env();
+        let start = env.block.height;
+        setup(deps.as_mut(), creator.as_str());
+
+        // two changes in one block, then a reset a few blocks later
+        env.block.height = start + 1;
+        for _ in 0..2 {
+            let info = mock_info(anyone.as_str(), &[]);
+            execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Increment {}).unwrap();
+        }
+        env.block.height = start + 5;
+        let msg = ExecuteMsg::Reset { count: 1 };
+        execute(deps.as_mut(), env, mock_info(creator.as_str(), &[]), msg).unwrap();
+
+        let count_at = |height| {
+            let msg = QueryMsg::GetCountAtHeight { height };
+            let res: GetCountAtHeightResp =
+                from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+            res.count
+        };
+        assert_eq!(None, count_at(start - 1));
+        assert_eq!(Some(7), count_at(start));
+        assert_eq!(Some(9), count_at(start + 1));
+        assert_eq!(Some(9), count_at(start + 4));
+        assert_eq!(Some(1), count_at(start + 5));
+        assert_eq!(Some(1), count_at(start + 100));
+
+        let msg = QueryMsg::CountHistory {
+            start_after: None,
+            limit: Some(3),
+        };
+        let res: CountHistoryResp =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let changes: Vec<_> = res
+            .changes
+            .iter()
+            .map(|c| (c.id, c.height, c.sender.clone(), c.count))
+            .collect();
+        assert_eq!(
+            vec![
+                (1, start, creator.clone(), 7),
+                (2, start + 1, anyone.clone(), 8),
+                (3, start + 1, anyone, 9),
+            ],
+            changes
+        );
+        let msg = QueryMsg::CountHistory {
+            start_after: Some(3),
+            limit: None,
+        };
+        let res: CountHistoryResp =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(1, res.changes.len());
+        assert_eq!(creator, res.changes[0].sender);
+        assert_eq!(1, res.changes[0].count);
+    }
 }
@@ -1,4 +1,4 @@
-use cosmwasm_std::{Addr, StdError};
+use cosmwasm_std::{Addr, OverflowError, StdError};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -6,6 +6,18 @@ pub enum ContractError {
     #[error("{0}")]
     StdError(#[from] StdError),
 
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
     #[error("{sender} is not contract admin")]
     Unauthorized { sender: Addr },
+
+    #[error("Counter {name} does not exist")]
+    CounterNotFound { name: String },
+
+    #[error("Counter {name} already exists")]
+    CounterExists { name: String },
+
+    #[error("Migrating a contract without an owner needs one in the migrate message")]
+    OwnerRequired {},
 }
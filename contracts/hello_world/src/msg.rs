@@ -1,4 +1,5 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Addr;
 
 #[cw_serde]
 #[derive(QueryResponses)]
@@ -7,6 +8,28 @@ pub enum QueryMsg {
     HelloWorld {},
     #[returns(GetCountResp)]
     GetCount {},
+    /// Count at the end of block `height`, none before the history starts
+    #[returns(GetCountAtHeightResp)]
+    GetCountAtHeight { height: u64 },
+    /// Changes of the count, oldest first
+    #[returns(CountHistoryResp)]
+    CountHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(GetOwnerResp)]
+    GetOwner {},
+    /// Exchange rate of an asset pair such as `ubtc:unusd` from the Nibiru oracle module
+    #[returns(GetPriceResp)]
+    GetPrice { pair: String },
+    #[returns(CounterResp)]
+    GetCounter { name: String },
+    /// Named counters in name order
+    #[returns(ListCountersResp)]
+    ListCounters {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 #[cw_serde]
@@ -24,13 +47,79 @@ pub struct GetCountResp {
     pub count: i32,
 }
 
+#[cw_serde]
+pub struct CounterResp {
+    pub name: String,
+    pub owner: Addr,
+    pub count: i32,
+}
+
+#[cw_serde]
+pub struct ListCountersResp {
+    pub counters: Vec<CounterResp>,
+}
+
+#[cw_serde]
+pub struct GetCountAtHeightResp {
+    pub count: Option<i32>,
+}
+
+#[cw_serde]
+pub struct CountChangeResp {
+    pub id: u64,
+    pub height: u64,
+    pub sender: Addr,
+    pub count: i32,
+}
+
+#[cw_serde]
+pub struct CountHistoryResp {
+    pub changes: Vec<CountChangeResp>,
+}
+
+#[cw_serde]
+pub struct GetOwnerResp {
+    pub owner: Addr,
+    pub pending_owner: Option<Addr>,
+}
+
 #[cw_serde]
 pub struct InstantiateMsg {
     pub count: i32,
+    /// Admin of the counter, the sender if not set
+    pub owner: Option<String>,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    /// Owner of contracts instantiated before they had one, ignored otherwise
+    pub owner: Option<String>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
     Increment {},
-    Reset { count: i32 },
+    /// Owner only
+    Reset {
+        count: i32,
+    },
+    /// Owner proposes a new owner, who takes over with `AcceptOwnership`
+    TransferOwnership {
+        new_owner: String,
+    },
+    AcceptOwnership {},
+    /// Creates a named counter owned by the sender
+    CreateCounter {
+        name: String,
+        count: i32,
+    },
+    /// Counter owner only
+    IncrementBy {
+        name: String,
+        delta: i32,
+    },
+    /// Counter owner only, decrements by one
+    Decrement {
+        name: String,
+    },
 }
@@ -0,0 +1,51 @@
+use cosmwasm_std::{Binary, QuerierWrapper, QueryRequest, StdResult};
+use serde::Deserialize;
+
+/// gRPC method of the Nibiru oracle module, it has to be on the chain's stargate query allow list
+pub const EXCHANGE_RATE_PATH: &str = "/nibiru.oracle.v1.Query/ExchangeRate";
+
+/// `nibiru.oracle.v1.QueryExchangeRateRequest`, stargate queries take it protobuf encoded
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExchangeRateRequest {
+    /// asset pair such as `ubtc:unusd`
+    pub pair: String,
+}
+
+impl ExchangeRateRequest {
+    pub fn encode(&self) -> Binary {
+        // field 1, length delimited
+        let mut buf = vec![0x0a];
+        encode_varint(self.pair.len() as u64, &mut buf);
+        buf.extend_from_slice(self.pair.as_bytes());
+        Binary::from(buf)
+    }
+}
+
+/// `nibiru.oracle.v1.QueryExchangeRateResponse`, stargate responses come back as JSON
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ExchangeRateResponse {
+    /// decimal string of the quote asset price
+    pub exchange_rate: String,
+}
+
+fn encode_varint(mut value: u64, buf: &mut Vec<u8>) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+pub fn query_exchange_rate(
+    querier: &QuerierWrapper,
+    pair: String,
+) -> StdResult<ExchangeRateResponse> {
+    // `Grpc` queries need the cosmwasm_2_0 capability, stargate queries work on every
+    // chain with the stargate capability
+    #[allow(deprecated)]
+    let request = QueryRequest::Stargate {
+        path: EXCHANGE_RATE_PATH.to_string(),
+        data: ExchangeRateRequest { pair }.encode(),
+    };
+    querier.query(&request)
+}
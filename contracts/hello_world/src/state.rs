@@ -1,9 +1,64 @@
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, StdResult, Storage};
+use cw_storage_plus::{Item, Map};
 use serde::{Deserialize, Serialize};
 
+/// Layout of the contract counter before hello_world tracked its version, `migrate`
+/// moves it to `COUNT`
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct State {
     pub count: i32,
 }
 
-pub const COUNT: Item<State> = Item::new("state");
+pub const LEGACY_COUNT: Item<State> = Item::new("state");
+
+pub const COUNT: Item<i32> = Item::new("count");
+
+/// Change of `COUNT`
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct CountChange {
+    pub height: u64,
+    pub sender: Addr,
+    /// count after the change
+    pub count: i32,
+}
+
+/// Every change of `COUNT` by id, in the order they happened
+pub const COUNT_HISTORY: Map<u64, CountChange> = Map::new("count_history");
+pub const COUNT_CHANGES: Item<u64> = Item::new("count_changes");
+/// `COUNT` at the end of each block it changed in
+pub const COUNT_AT_HEIGHT: Map<u64, i32> = Map::new("count_at_height");
+
+/// Admin of the counter, the only one allowed to `Reset` it
+pub const OWNER: Item<Addr> = Item::new("owner");
+/// Proposed owner until it accepts the ownership
+pub const PENDING_OWNER: Item<Addr> = Item::new("pending_owner");
+
+/// Counter created by a user under a name of their choice
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Counter {
+    /// the only one allowed to change the count
+    pub owner: Addr,
+    pub count: i32,
+}
+
+pub const COUNTERS: Map<String, Counter> = Map::new("counters");
+
+/// Saves `COUNT` and records the change in its history
+pub fn save_count(
+    storage: &mut dyn Storage,
+    height: u64,
+    sender: &Addr,
+    count: i32,
+) -> StdResult<()> {
+    COUNT.save(storage, &count)?;
+
+    let id = COUNT_CHANGES.may_load(storage)?.unwrap_or_default() + 1;
+    COUNT_CHANGES.save(storage, &id)?;
+    let change = CountChange {
+        height,
+        sender: sender.clone(),
+        count,
+    };
+    COUNT_HISTORY.save(storage, id, &change)?;
+    COUNT_AT_HEIGHT.save(storage, height, &count)
+}
@@ -0,0 +1,13 @@
+use cosmwasm_schema::write_api;
+use cosmwasm_std::Empty;
+
+use jaken::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+fn main() {
+    write_api! {
+        instantiate: InstantiateMsg,
+        execute: ExecuteMsg,
+        query: QueryMsg,
+        migrate: Empty,
+    }
+}
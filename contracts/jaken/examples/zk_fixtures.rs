@@ -0,0 +1,91 @@
+// Runs a deterministic trusted setup for `jaken::zk::OutcomeCircuit` and writes the
+// verifying key and a few proofs to `testdata/zk_outcome.json` for the contract tests.
+// The setup randomness is public, never use this verifying key on chain.
+use std::fs;
+use std::path::Path;
+
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::Groth16;
+use ark_serialize::CanonicalSerialize;
+use ark_snark::SNARK;
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use ark_std::UniformRand;
+
+use cosmwasm_std::{to_json_string, Binary};
+use serde::Serialize;
+
+use jaken::state::{GameMove, GameResult};
+use jaken::zk::{field_to_bytes, mimc_commit, move_index, outcome_index, OutcomeCircuit};
+
+#[derive(Serialize)]
+struct ZkCase {
+    host_move: GameMove,
+    opp_move: GameMove,
+    result: GameResult,
+    commitment: Binary,
+    proof: Binary,
+}
+
+#[derive(Serialize)]
+struct ZkFixtures {
+    verifying_key: Binary,
+    cases: Vec<ZkCase>,
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0x6a616b656e);
+    let (pk, vk) =
+        Groth16::<Bn254>::circuit_specific_setup(OutcomeCircuit::default(), &mut rng).unwrap();
+
+    let mut vk_bytes = vec![];
+    vk.serialize_compressed(&mut vk_bytes).unwrap();
+
+    let cases = [
+        (
+            GameMove::Rock {},
+            GameMove::Scissors {},
+            GameResult::HostWins {},
+        ),
+        (
+            GameMove::Rock {},
+            GameMove::Paper {},
+            GameResult::OpponentWins {},
+        ),
+        (GameMove::Paper {}, GameMove::Paper {}, GameResult::Tie {}),
+    ];
+    let cases = cases
+        .into_iter()
+        .map(|(host_move, opp_move, result)| {
+            let salt = Fr::rand(&mut rng);
+            let commitment = mimc_commit(&host_move, salt).unwrap();
+            let circuit = OutcomeCircuit {
+                commitment: Some(commitment),
+                opp_move: move_index(&opp_move),
+                outcome: outcome_index(&result),
+                host_move: move_index(&host_move),
+                salt: Some(salt),
+            };
+            let proof = Groth16::<Bn254>::prove(&pk, circuit, &mut rng).unwrap();
+            let mut proof_bytes = vec![];
+            proof.serialize_compressed(&mut proof_bytes).unwrap();
+
+            ZkCase {
+                host_move,
+                opp_move,
+                result,
+                commitment: Binary::from(field_to_bytes(&commitment)),
+                proof: Binary::from(proof_bytes),
+            }
+        })
+        .collect();
+
+    let fixtures = ZkFixtures {
+        verifying_key: Binary::from(vk_bytes),
+        cases,
+    };
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/zk_outcome.json");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, to_json_string(&fixtures).unwrap()).unwrap();
+    println!("wrote {}", path.display());
+}
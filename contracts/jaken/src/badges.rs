@@ -0,0 +1,137 @@
+use std::marker::PhantomData;
+
+use cosmwasm_std::{to_json_vec, Addr, Empty, StdResult, Storage, SubMsg};
+use cw721_base::helpers::Cw721Contract;
+use cw721_base::msg::ExecuteMsg as Cw721ExecuteMsg;
+use cw721_base::Extension;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::state::{next_badge_id, PlayerStats, STATE};
+
+/// Reply id of badge mints, a failing mint is reported instead of reverting the game
+pub const BADGE_REPLY_ID: u64 = 1;
+
+/// Consecutive PvP wins that earn a streak badge
+pub const STREAK_BADGES: [u32; 3] = [3, 5, 10];
+
+/// Achievement a badge NFT is minted for
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Badge {
+    /// finished a first PvP game
+    FirstGame {},
+    /// won `wins` PvP games in a row
+    WinStreak { wins: u32 },
+    /// won a tournament
+    TournamentChampion { tournament_id: u64 },
+}
+
+impl Badge {
+    pub fn name(&self) -> String {
+        match self {
+            Badge::FirstGame {} => "Jaken Rookie".to_string(),
+            Badge::WinStreak { wins } => format!("Jaken Streak of {}", wins),
+            Badge::TournamentChampion { tournament_id } => {
+                format!("Jaken Champion #{}", tournament_id)
+            }
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Badge::FirstGame {} => "Finished a first game of jaken".to_string(),
+            Badge::WinStreak { wins } => format!("Won {} jaken games in a row", wins),
+            Badge::TournamentChampion { tournament_id } => {
+                format!("Won jaken tournament {}", tournament_id)
+            }
+        }
+    }
+
+    fn attributes(&self) -> Vec<Trait> {
+        let achievement = match self {
+            Badge::FirstGame {} => "first_game",
+            Badge::WinStreak { .. } => "win_streak",
+            Badge::TournamentChampion { .. } => "tournament_champion",
+        };
+        let mut attributes = vec![Trait::new("achievement", achievement)];
+        match self {
+            Badge::FirstGame {} => {}
+            Badge::WinStreak { wins } => attributes.push(Trait::new("wins", wins)),
+            Badge::TournamentChampion { tournament_id } => {
+                attributes.push(Trait::new("tournament_id", tournament_id))
+            }
+        }
+        attributes
+    }
+
+    /// ERC721 metadata JSON inlined as a data URI, badges need no off chain hosting
+    pub fn token_uri(&self) -> StdResult<String> {
+        let metadata = Metadata {
+            name: self.name(),
+            description: self.description(),
+            attributes: self.attributes(),
+        };
+        Ok(format!(
+            "data:application/json;base64,{}",
+            base64::encode(to_json_vec(&metadata)?)
+        ))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Metadata {
+    pub name: String,
+    pub description: String,
+    pub attributes: Vec<Trait>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Trait {
+    pub trait_type: String,
+    pub value: String,
+}
+
+impl Trait {
+    fn new(trait_type: &str, value: impl ToString) -> Self {
+        Trait {
+            trait_type: trait_type.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+/// Badges a player earned with the game just added to `stats`
+pub fn game_badges(stats: &PlayerStats) -> Vec<Badge> {
+    let mut badges = vec![];
+    if stats.wins + stats.losses + stats.ties == 1 {
+        badges.push(Badge::FirstGame {});
+    }
+    if STREAK_BADGES.contains(&stats.streak) {
+        badges.push(Badge::WinStreak { wins: stats.streak });
+    }
+    badges
+}
+
+/// Mints `badge` to `owner` through the badge collection jaken is the minter of,
+/// nothing is minted while no collection is configured
+pub fn mint_badge(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    badge: &Badge,
+) -> StdResult<Option<SubMsg>> {
+    let collection = match STATE.load(storage)?.badge_collection {
+        Some(collection) => collection,
+        None => return Ok(None),
+    };
+
+    let mint_msg = Cw721ExecuteMsg::<Extension, Empty>::Mint {
+        token_id: next_badge_id(storage)?.to_string(),
+        owner: owner.to_string(),
+        token_uri: Some(badge.token_uri()?),
+        extension: None,
+    };
+    Cw721Contract::<Empty, Empty>(collection, PhantomData, PhantomData)
+        .call(mint_msg)
+        .map(|msg| Some(SubMsg::reply_on_error(msg, BADGE_REPLY_ID)))
+}
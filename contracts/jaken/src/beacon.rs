@@ -0,0 +1,85 @@
+use cosmwasm_std::{Api, Binary, HashFunction, Timestamp, BLS12_381_G2_GENERATOR};
+
+use crate::error::ContractError;
+use crate::state::BeaconSchedule;
+use crate::utils::{sha_256, SHA256_HASH_SIZE};
+
+/// Length of a compressed BLS12-381 G2 point, the beacon public key
+pub const PUBKEY_SIZE: usize = 96;
+/// Length of a compressed BLS12-381 G1 point, the signature of a round
+pub const SIGNATURE_SIZE: usize = 48;
+
+/// Hash to curve domain of drand's `bls-unchained-g1-rfc9380` scheme (quicknet)
+pub const DST: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// Seconds between two rounds of a beacon configured without a schedule
+pub const DEFAULT_PERIOD: u64 = 30;
+
+/// Schedule of a beacon configured without one, its first round is due one period after `now`
+pub fn default_schedule(now: Timestamp) -> BeaconSchedule {
+    BeaconSchedule {
+        genesis: now.plus_seconds(DEFAULT_PERIOD),
+        period: DEFAULT_PERIOD,
+    }
+}
+
+pub fn validate_schedule(schedule: &BeaconSchedule) -> Result<(), ContractError> {
+    match schedule.period {
+        0 => Err(ContractError::InvalidBeaconPeriod {}),
+        _ => Ok(()),
+    }
+}
+
+/// Time the beacon publishes `round` at, like drand it publishes round 1 at genesis
+pub fn round_time(schedule: &BeaconSchedule, round: u64) -> Timestamp {
+    let rounds = round.saturating_sub(1);
+    schedule
+        .genesis
+        .plus_seconds(rounds.saturating_mul(schedule.period))
+}
+
+/// The beacon signs `sha256(round)` for every round, like drand does in unchained mode
+pub fn round_message(round: u64) -> [u8; SHA256_HASH_SIZE] {
+    sha_256(&round.to_be_bytes())
+}
+
+/// The public key has to be a point of the BLS12-381 G2 subgroup
+pub fn validate_pubkey(api: &dyn Api, pubkey: &Binary) -> Result<(), ContractError> {
+    if pubkey.len() != PUBKEY_SIZE {
+        return Err(ContractError::InvalidBeaconKey {});
+    }
+    api.bls12_381_aggregate_g2(pubkey)
+        .map_err(|_| ContractError::InvalidBeaconKey {})?;
+    Ok(())
+}
+
+/// Verifies a signed beacon round and derives its randomness, `sha256(signature)` as drand does.
+/// BLS signatures are unique: a round has a single valid signature, so whoever holds the
+/// key can withhold a round but cannot pick its randomness among several signatures.
+pub fn verify_round(
+    api: &dyn Api,
+    pubkey: &[u8],
+    round: u64,
+    signature: &[u8],
+) -> Result<Binary, ContractError> {
+    if signature.len() != SIGNATURE_SIZE {
+        return Err(ContractError::InvalidBeaconSignature {});
+    }
+    let message = api
+        .bls12_381_hash_to_g1(HashFunction::Sha256, &round_message(round), DST)
+        .map_err(|_| ContractError::InvalidBeaconSignature {})?;
+    // e(signature, g2) == e(H(round), pubkey)
+    let valid = api
+        .bls12_381_pairing_equality(signature, &BLS12_381_G2_GENERATOR, &message, pubkey)
+        .map_err(|_| ContractError::InvalidBeaconSignature {})?;
+    if !valid {
+        return Err(ContractError::InvalidBeaconSignature {});
+    }
+
+    Ok(Binary::from(sha_256(signature)))
+}
+
+/// Randomness of a single bet, so bets settled against the same round draw different moves
+pub fn bet_randomness(round_randomness: &[u8], bet_id: u64) -> [u8; SHA256_HASH_SIZE] {
+    sha_256(&[round_randomness, &bet_id.to_be_bytes()].concat())
+}
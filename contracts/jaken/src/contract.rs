@@ -1,44 +1,206 @@
+use std::cmp::Ordering;
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coins, to_json_binary, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError,
-    StdResult,
+    from_json, to_json_binary, Addr, Binary, Deps, DepsMut, Empty, Env, Int128, MessageInfo, Order,
+    Reply, Response, StdError, StdResult, Storage, SubMsg, Uint128,
 };
 use cosmwasm_std::{Coin, CosmosMsg};
-use cw2::set_contract_version;
+use cw2::{ensure_from_older_version, set_contract_version};
+use cw20::Cw20ReceiveMsg;
+use cw721::Cw721ReceiveMsg;
+use cw_storage_plus::Bound;
+use cw_utils::Duration;
 
+use crate::badges::{game_badges, mint_badge, Badge, BADGE_REPLY_ID};
+use crate::beacon::{
+    bet_randomness, default_schedule, round_time, validate_pubkey, validate_schedule, verify_round,
+};
 use crate::error::ContractError;
-use crate::helpers::{validate_balance, validate_sent_funds};
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ResultResponse};
-use crate::state::{rand_move, Game, GameMove, GameResult, Random, State, GAME, RANDOM, STATE};
-use crate::utils::{sha_256, Prng};
+use crate::helpers::{
+    cw20_address, cw20_denom, send_coin, transfer_nft, validate_house_reserve, validate_sent_funds,
+};
+use crate::msg::{
+    BadgeCollectionResponse, BeaconResponse, BetLimitResponse, BetLimitsResponse, CircuitsResponse,
+    CollectionsResponse, Cw20TokensResponse, DenomStatsResponse, ExecuteMsg, GameResponse,
+    HouseStatsResponse, InstantiateMsg, LeaderboardResponse, NftReceiveMsg, OpenChallengesResponse,
+    PausedResponse, QueryMsg, ReceiveMsg, ResultResponse, TournamentResponse, TournamentsResponse,
+};
+use crate::rating::{apply_change, rating_change, LOSS, TIE, WIN};
+use crate::rules::GameKind;
+use crate::state::{
+    active_games, archive_game, challenges, next_bet_id, next_game_id, next_tournament_id,
+    player_stats, players, record_house_bet, release_funds, reserve_funds, BeaconSchedule,
+    BetLimit, Challenge, Game, GameMove, GameResult, LegacyState, MatchRef, Nft, PendingBet,
+    Random, State, Tournament, TournamentMatch, TournamentStatus, ACCEPTED_CW20, ACCEPTED_CW721,
+    BEACON_ROUNDS, BEACON_SCHEDULE, BET_LIMITS, GAMES, HOUSE_STATS, LAST_GAME, LATEST_ROUND,
+    LEGACY_GAMES, LEGACY_STATE, OWNERSHIP, PENDING_BETS, PLAYER_GAMES, RANDOM, RESERVED, STATE,
+    TOURNAMENTS, TOURNAMENT_MATCHES, VERIFYING_KEYS,
+};
+use crate::tournament::{pair_players, prize_payouts, validate_settings, TOTAL_BPS};
+use crate::utils::{commit_move, sha_256, Prng, SHA256_HASH_SIZE};
+use crate::zk::{field_from_bytes, validate_verifying_key, verify_outcome};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:rps";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 100;
+
+// players get one day per move unless configured otherwise
+const DEFAULT_GAME_TIMEOUT: Duration = Duration::Time(24 * 60 * 60);
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+    let game_timeout = msg.game_timeout.unwrap_or(DEFAULT_GAME_TIMEOUT);
+    validate_timeout(&game_timeout)?;
+
+    if let Some(pubkey) = &msg.beacon_pubkey {
+        validate_pubkey(deps.api, pubkey)?;
+        let schedule = msg
+            .beacon_schedule
+            .unwrap_or_else(|| default_schedule(env.block.time));
+        validate_schedule(&schedule)?;
+        BEACON_SCHEDULE.save(deps.storage, &schedule)?;
+    }
+    let house_edge_bps = msg.house_edge_bps.unwrap_or_default();
+    validate_house_edge(house_edge_bps)?;
+
+    let badge_collection = msg
+        .badge_collection
+        .map(|collection| deps.api.addr_validate(&collection))
+        .transpose()?;
+
+    let ownership = cw_ownable::initialize_owner(deps.storage, deps.api, Some(msg.owner.as_str()))?;
     let state = State {
-        owner: info.sender.clone(),
+        game_timeout,
+        beacon_pubkey: msg.beacon_pubkey,
+        house_edge_bps,
+        paused: false,
+        badge_collection,
     };
 
-    let rand_state = Random {
-        prng_seed: sha_256(base64::encode(msg.prng_seed.clone()).as_bytes()).to_vec(),
-        entropy: msg.prng_seed.as_bytes().to_vec(),
-    };
+    for key in msg.verifying_keys.unwrap_or_default() {
+        validate_verifying_key(&key.verifying_key)?;
+        VERIFYING_KEYS.save(deps.storage, &key.circuit, &key.verifying_key)?;
+    }
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    RANDOM.save(deps.storage, &rand_state)?;
+    RANDOM.save(deps.storage, &seed_random(&msg.prng_seed))?;
     STATE.save(deps.storage, &state)?;
 
     Ok(Response::new()
         .add_attribute("method", "instantiate")
-        .add_attribute("owner", info.sender))
+        .add_attribute("sender", info.sender)
+        .add_attributes(ownership.into_attributes()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: Empty) -> Result<Response, ContractError> {
+    // only jaken can be upgraded, and never to an older version
+    let version = ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let res = Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", version.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION);
+    // the first release kept its owner in `State`, later ones with cw-ownable
+    if OWNERSHIP.exists(deps.storage) {
+        return Ok(res);
+    }
+    let legacy = LEGACY_STATE.load(deps.storage)?;
+    migrate_legacy_state(deps, legacy, res)
+}
+
+/// The first jaken release kept the owner in `State` and had no settings. Its games
+/// carry the host move in the clear and cannot be played on, they are dropped. They
+/// were started without funds, so there is nothing to refund.
+fn migrate_legacy_state(
+    deps: DepsMut,
+    legacy: LegacyState,
+    res: Response,
+) -> Result<Response, ContractError> {
+    let ownership =
+        cw_ownable::initialize_owner(deps.storage, deps.api, Some(legacy.owner.as_str()))?;
+    let state = State {
+        game_timeout: DEFAULT_GAME_TIMEOUT,
+        beacon_pubkey: None,
+        house_edge_bps: 0,
+        paused: false,
+        badge_collection: None,
+    };
+    STATE.save(deps.storage, &state)?;
+
+    let games = LEGACY_GAMES
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for key in &games {
+        LEGACY_GAMES.remove(deps.storage, key.clone());
+    }
+
+    Ok(res
+        .add_attribute("dropped_games", games.len().to_string())
+        .add_attributes(ownership.into_attributes()))
+}
+
+/// Badge mints are sent with `reply_on_error`, a collection that refuses to mint
+/// costs the player the badge but never reverts the game that earned it
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        BADGE_REPLY_ID => Ok(Response::new()
+            .add_attribute("action", "badge_mint_failed")
+            .add_attribute("error", msg.result.unwrap_err())),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+fn seed_random(prng_seed: &str) -> Random {
+    Random {
+        prng_seed: sha_256(base64::encode(prng_seed).as_bytes()).to_vec(),
+        entropy: prng_seed.as_bytes().to_vec(),
+    }
+}
+
+/// Active game between `host` and `opponent`, `game_id` picks one when they play several
+fn load_game(
+    storage: &dyn Storage,
+    host: &Addr,
+    opponent: &Addr,
+    game_id: Option<u64>,
+) -> Result<Game, ContractError> {
+    let game = match game_id {
+        Some(id) => active_games().may_load(storage, id)?,
+        None => {
+            let mut games = active_games()
+                .idx
+                .pair
+                .prefix((host.clone(), opponent.clone()))
+                .range(storage, None, None, Order::Ascending)
+                .take(2)
+                .collect::<StdResult<Vec<_>>>()?;
+            if games.len() > 1 {
+                return Err(ContractError::GameIdRequired {});
+            }
+            games.pop().map(|(_, game)| game)
+        }
+    };
+    game.filter(|game| game.host == *host && game.opponent == *opponent)
+        .ok_or(ContractError::GameNotFound {})
+}
+
+fn assert_not_paused(storage: &dyn Storage) -> Result<(), ContractError> {
+    match STATE.load(storage)?.paused {
+        true => Err(ContractError::Paused {}),
+        false => Ok(()),
+    }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -48,324 +210,5597 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // cw20 denoms are only credited through `Receive`
+    if let Some(coin) = info
+        .funds
+        .iter()
+        .find(|coin| cw20_address(&coin.denom).is_some())
+    {
+        return Err(ContractError::DenomNotAllowed {
+            denom: coin.denom.clone(),
+        });
+    }
+
     match msg {
         ExecuteMsg::StartGame {
+            opponent,
+            commitment,
+            timeout,
+            circuit,
+            rounds,
+            kind,
+        } => try_start_game(
+            deps, info, env, opponent, commitment, timeout, circuit, rounds, kind, None,
+        ),
+
+        ExecuteMsg::Respond {
+            host,
+            second_move,
+            game_id,
+        } => try_respond_to_game(deps, info, env, host, second_move, game_id, None),
+
+        ExecuteMsg::Reveal {
             opponent,
             first_move,
-        } => try_start_game(deps, info, opponent, first_move),
+            salt,
+            next_commitment,
+            game_id,
+        } => try_reveal(
+            deps,
+            info,
+            env,
+            opponent,
+            first_move,
+            salt,
+            next_commitment,
+            game_id,
+        ),
+
+        ExecuteMsg::ProveOutcome {
+            opponent,
+            result,
+            proof,
+            next_commitment,
+            game_id,
+        } => try_prove_outcome(
+            deps,
+            info,
+            env,
+            opponent,
+            result,
+            proof,
+            next_commitment,
+            game_id,
+        ),
+
+        ExecuteMsg::CancelGame { opponent, game_id } => {
+            try_cancel_game(deps, info, opponent, game_id)
+        }
+
+        ExecuteMsg::CancelChallenge { game_id } => try_cancel_challenge(deps, info, game_id),
+
+        ExecuteMsg::Receive(msg) => try_receive(deps, info, env, msg),
+
+        ExecuteMsg::AllowCw20 { token } => try_allow_cw20(deps, info, token),
+
+        ExecuteMsg::RemoveCw20 { token } => try_remove_cw20(deps, info, token),
+
+        ExecuteMsg::ReceiveNft(msg) => try_receive_nft(deps, info, env, msg),
 
-        ExecuteMsg::Respond { host, second_move } => {
-            try_respond_to_game(deps, info, host, second_move)
+        ExecuteMsg::AllowCollection { collection } => try_allow_collection(deps, info, collection),
+
+        ExecuteMsg::RemoveCollection { collection } => {
+            try_remove_collection(deps, info, collection)
+        }
+
+        ExecuteMsg::CreateTournament {
+            entry_fee,
+            max_players,
+            organizer_cut_bps,
+            prize_split,
+            timeout,
+        } => try_create_tournament(
+            deps,
+            info,
+            entry_fee,
+            max_players,
+            organizer_cut_bps,
+            prize_split,
+            timeout,
+        ),
+
+        ExecuteMsg::RegisterTournament { tournament_id } => {
+            try_register_tournament(deps, info, env, tournament_id)
+        }
+
+        ExecuteMsg::StartTournament { tournament_id } => {
+            try_start_tournament(deps, info, env, tournament_id)
+        }
+
+        ExecuteMsg::CancelTournament { tournament_id } => {
+            try_cancel_tournament(deps, info, tournament_id)
         }
 
+        ExecuteMsg::StartMatch {
+            tournament_id,
+            commitment,
+        } => try_start_match(deps, info, env, tournament_id, commitment),
+
+        ExecuteMsg::ClaimMatch {
+            tournament_id,
+            slot,
+        } => try_claim_match(deps, env, tournament_id, slot),
+
+        ExecuteMsg::ClaimTimeout {
+            host,
+            opponent,
+            game_id,
+        } => try_claim_timeout(deps, info, env, host, opponent, game_id),
+
         ExecuteMsg::BetToken {
             first_move,
             entropy,
-        } => try_bet_token(deps, info, env, first_move, entropy),
+            kind,
+        } => try_bet_token(deps, info, env, first_move, entropy, kind),
+
+        ExecuteMsg::SubmitBeacon { round, signature } => {
+            try_submit_beacon(deps, env, round, signature)
+        }
+
+        ExecuteMsg::SettleBet { bet_id } => try_settle_bet(deps, bet_id),
 
         ExecuteMsg::Withdraw { coin } => try_withdraw(deps, info, env, coin),
+
+        ExecuteMsg::SetBetLimit {
+            denom,
+            min_bet,
+            max_bet,
+        } => try_set_bet_limit(deps, info, denom, min_bet, max_bet),
+
+        ExecuteMsg::RemoveBetLimit { denom } => try_remove_bet_limit(deps, info, denom),
+
+        ExecuteMsg::SetHouseEdge { house_edge_bps } => {
+            try_set_house_edge(deps, info, house_edge_bps)
+        }
+
+        ExecuteMsg::SetPaused { paused } => try_set_paused(deps, info, paused),
+
+        ExecuteMsg::SetBadgeCollection { collection } => {
+            try_set_badge_collection(deps, info, collection)
+        }
+
+        ExecuteMsg::Reseed { prng_seed } => try_reseed(deps, info, prng_seed),
+
+        ExecuteMsg::UpdateOwnership(action) => try_update_ownership(deps, env, info, action),
+
+        ExecuteMsg::SetBeacon { pubkey, schedule } => {
+            try_set_beacon(deps, info, env, pubkey, schedule)
+        }
     }
 }
 
-pub fn try_withdraw(
+/// Plays the embedded message with the received tokens as its funds
+pub fn try_receive(
     deps: DepsMut,
     info: MessageInfo,
     env: Env,
-    coin: Coin,
+    wrapper: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
-    if info.sender != state.owner {
-        return Err(ContractError::Unauthorized {});
+    // the sender of `Receive` is the token contract
+    if !ACCEPTED_CW20.has(deps.storage, &info.sender) {
+        return Err(ContractError::Cw20NotAccepted {
+            token: info.sender.to_string(),
+        });
     }
+    let info = MessageInfo {
+        sender: deps.api.addr_validate(&wrapper.sender)?,
+        funds: vec![Coin::new(wrapper.amount, cw20_denom(&info.sender))],
+    };
 
-    let contract_addr = env.contract.address.clone();
-    let amount = coin.amount.u128();
-    let denom = coin.denom.clone();
-    validate_balance(&deps, &contract_addr, &denom, amount)?;
-
-    if amount == 0 {
-        return Err(ContractError::NoFunds {});
+    match from_json(&wrapper.msg)? {
+        ReceiveMsg::StartGame {
+            opponent,
+            commitment,
+            timeout,
+            circuit,
+            rounds,
+            kind,
+        } => try_start_game(
+            deps, info, env, opponent, commitment, timeout, circuit, rounds, kind, None,
+        ),
+        ReceiveMsg::Respond {
+            host,
+            second_move,
+            game_id,
+        } => try_respond_to_game(deps, info, env, host, second_move, game_id, None),
+        ReceiveMsg::BetToken {
+            first_move,
+            entropy,
+            kind,
+        } => try_bet_token(deps, info, env, first_move, entropy, kind),
+        ReceiveMsg::RegisterTournament { tournament_id } => {
+            try_register_tournament(deps, info, env, tournament_id)
+        }
     }
+}
 
-    let res = Response::new()
-        .add_message(BankMsg::Send {
-            to_address: info.sender.to_string(),
-            amount: coins(amount.into(), denom.clone()),
-        })
-        .add_attribute("action", "withdraw")
-        .add_attribute("amount", amount.to_string())
-        .add_attribute("denom", denom);
+pub fn try_allow_cw20(
+    deps: DepsMut,
+    info: MessageInfo,
+    token: String,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
-    Ok(res)
+    let token = deps.api.addr_validate(&token)?;
+    ACCEPTED_CW20.save(deps.storage, &token, &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "allow_cw20")
+        .add_attribute("token", token.clone())
+        .add_attribute("denom", cw20_denom(&token)))
 }
 
-pub fn try_bet_token(
+pub fn try_remove_cw20(
     deps: DepsMut,
     info: MessageInfo,
-    env: Env,
-    opp_move: GameMove,
-    entropy: String,
+    token: String,
 ) -> Result<Response, ContractError> {
-    // validate host address
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
-    let fund = validate_sent_funds(info.funds)?;
-    let contract_addr = env.contract.address.clone();
+    // escrowed tokens are still paid out, only new games and bets are refused
+    let token = deps.api.addr_validate(&token)?;
+    ACCEPTED_CW20.remove(deps.storage, &token);
 
-    // Validate contract balance
-    validate_balance(&deps, &contract_addr, &fund.denom, fund.amount.u128())?;
-
-    let opponent = info.sender.clone();
-    let mut rand_state = RANDOM.load(deps.storage)?;
-    let rng = Prng::new_rand_bytes(&rand_state.entropy, (&entropy).as_ref());
-    rand_state.entropy = rng.clone();
-    RANDOM.save(deps.storage, &rand_state)?;
+    Ok(Response::new()
+        .add_attribute("action", "remove_cw20")
+        .add_attribute("token", token))
+}
 
-    let contract_move: GameMove = rand_move(&rng);
-
-    let result = match opp_move {
-        GameMove::Rock {} => match contract_move {
-            GameMove::Rock {} => GameResult::Tie {},
-            GameMove::Paper {} => GameResult::ContractWins {},
-            GameMove::Scissors {} => GameResult::PlayerWins {},
-        },
-        GameMove::Paper {} => match contract_move {
-            GameMove::Rock {} => GameResult::PlayerWins {},
-            GameMove::Paper {} => GameResult::Tie {},
-            GameMove::Scissors {} => GameResult::ContractWins {},
-        },
-        GameMove::Scissors {} => match contract_move {
-            GameMove::Rock {} => GameResult::ContractWins {},
-            GameMove::Paper {} => GameResult::PlayerWins {},
-            GameMove::Scissors {} => GameResult::Tie {},
-        },
+/// Plays the embedded message with the received token as its stake
+pub fn try_receive_nft(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    wrapper: Cw721ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // the sender of `ReceiveNft` is the collection, jaken already owns the token
+    if !ACCEPTED_CW721.has(deps.storage, &info.sender) {
+        return Err(ContractError::CollectionNotAccepted {
+            collection: info.sender.to_string(),
+        });
+    }
+    let nft = Nft {
+        collection: info.sender,
+        token_id: wrapper.token_id,
     };
-    let denom = fund.denom.clone();
-    let amount = fund.amount.u128() as u64;
-
-    let message = match result {
-        GameResult::PlayerWins {} => Some(BankMsg::Send {
-            to_address: opponent.to_string(),
-            amount: coins((amount * 2).into(), denom),
-        }),
-        GameResult::Tie {} => Some(BankMsg::Send {
-            to_address: opponent.to_string(),
-            amount: coins(amount.into(), denom),
-        }),
-        _ => None,
+    let info = MessageInfo {
+        sender: deps.api.addr_validate(&wrapper.sender)?,
+        funds: vec![],
     };
 
-    if let Some(msg) = message {
-        Ok(Response::new()
-            .add_message(CosmosMsg::Bank(msg))
-            .add_attribute("action", "bet_token")
-            .add_attribute("result", result.to_string()))
-    } else {
-        Ok(Response::new()
-            .add_attribute("action", "bet_token")
-            .add_attribute("result", result.to_string()))
+    match from_json(&wrapper.msg)? {
+        NftReceiveMsg::StartGame {
+            opponent,
+            commitment,
+            timeout,
+            circuit,
+            rounds,
+            kind,
+        } => try_start_game(
+            deps,
+            info,
+            env,
+            opponent,
+            commitment,
+            timeout,
+            circuit,
+            rounds,
+            kind,
+            Some(nft),
+        ),
+        NftReceiveMsg::Respond {
+            host,
+            second_move,
+            game_id,
+        } => try_respond_to_game(deps, info, env, host, second_move, game_id, Some(nft)),
     }
 }
 
-pub fn try_respond_to_game(
+pub fn try_allow_collection(
     deps: DepsMut,
     info: MessageInfo,
-    host: String,
-    second_move: GameMove,
+    collection: String,
 ) -> Result<Response, ContractError> {
-    // validate host address
-    let host_addr = deps.api.addr_validate(&host)?;
-    let responder_addr = info.sender;
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
-    //load game by passing host addr and opponent addr
-    let mut game = GAME.load(deps.storage, (host_addr.clone(), responder_addr.clone()))?;
+    let collection = deps.api.addr_validate(&collection)?;
+    ACCEPTED_CW721.save(deps.storage, &collection, &Empty {})?;
 
-    if game.opponent != responder_addr {
-        return Err(ContractError::UnauthorizedOpponent {});
-    } else {
-        game.opp_move = Some(second_move);
-        game.result = get_game_result(game.clone());
+    Ok(Response::new()
+        .add_attribute("action", "allow_collection")
+        .add_attribute("collection", collection))
+}
+
+pub fn try_remove_collection(
+    deps: DepsMut,
+    info: MessageInfo,
+    collection: String,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    // escrowed tokens are still handed out, only new stakes are refused
+    let collection = deps.api.addr_validate(&collection)?;
+    ACCEPTED_CW721.remove(deps.storage, &collection);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_collection")
+        .add_attribute("collection", collection))
+}
+
+pub fn try_set_bet_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    min_bet: Uint128,
+    max_bet: Uint128,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    if min_bet.is_zero() || min_bet > max_bet {
+        return Err(ContractError::InvalidBetLimit {});
     }
 
-    let game_response = ResultResponse {
-        result: game.result.unwrap(),
-    };
-    GAME.remove(deps.storage, (host_addr, responder_addr));
+    BET_LIMITS.save(deps.storage, &denom, &BetLimit { min_bet, max_bet })?;
 
-    Ok(game_response.into())
+    Ok(Response::new()
+        .add_attribute("action", "set_bet_limit")
+        .add_attribute("denom", denom)
+        .add_attribute("min_bet", min_bet)
+        .add_attribute("max_bet", max_bet))
 }
 
-pub fn get_game_result(game: Game) -> Option<GameResult> {
-    let host_move = game.host_move;
-    let opp_move = game.opp_move?;
+pub fn try_remove_bet_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
-    match host_move {
-        GameMove::Rock {} => match opp_move {
-            GameMove::Rock {} => Some(GameResult::Tie {}),
-            GameMove::Paper {} => Some(GameResult::OpponentWins {}),
-            GameMove::Scissors {} => Some(GameResult::HostWins {}),
-        },
-        GameMove::Paper {} => match opp_move {
-            GameMove::Rock {} => Some(GameResult::HostWins {}),
-            GameMove::Paper {} => Some(GameResult::Tie {}),
-            GameMove::Scissors {} => Some(GameResult::OpponentWins {}),
-        },
-        GameMove::Scissors {} => match opp_move {
-            GameMove::Rock {} => Some(GameResult::OpponentWins {}),
-            GameMove::Paper {} => Some(GameResult::HostWins {}),
-            GameMove::Scissors {} => Some(GameResult::Tie {}),
-        },
-    }
+    BET_LIMITS.remove(deps.storage, &denom);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_bet_limit")
+        .add_attribute("denom", denom))
 }
 
-pub fn try_start_game(
+pub fn try_set_paused(
     deps: DepsMut,
     info: MessageInfo,
-    opponent: String,
-    first_move: GameMove,
+    paused: bool,
 ) -> Result<Response, ContractError> {
-    // validate opponent address
-    let opponent_addr = deps.api.addr_validate(&opponent)?;
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
-    // try to start game, if game is already started with given host it will throw error, otherwise it will create a new game object and save it under host key
-    let start_game = |host: Option<Game>| -> Result<Game, ContractError> {
-        match host {
-            Some(_) => Err(ContractError::AlreadyStarted {}),
-            None => {
-                let game = Game {
-                    host: info.sender.clone(),
-                    opponent: opponent_addr.clone(),
-                    host_move: first_move.clone(),
-                    opp_move: None,
-                    result: None,
-                };
+    let mut state = STATE.load(deps.storage)?;
+    state.paused = paused;
+    STATE.save(deps.storage, &state)?;
 
-                Ok(game)
-            }
-        }
-    };
+    Ok(Response::new()
+        .add_attribute("action", "set_paused")
+        .add_attribute("paused", paused.to_string()))
+}
 
-    GAME.update(
-        deps.storage,
-        (info.sender.clone(), opponent_addr.clone()),
-        start_game,
-    )?;
+pub fn try_set_badge_collection(
+    deps: DepsMut,
+    info: MessageInfo,
+    collection: Option<String>,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
-    let res = Response::new()
-        .add_attribute("action", "start_game")
-        .add_attribute("host", info.sender)
-        .add_attribute("opponent", opponent_addr)
-        .add_attribute("host_move", first_move.to_string());
+    let mut state = STATE.load(deps.storage)?;
+    state.badge_collection = collection
+        .map(|collection| deps.api.addr_validate(&collection))
+        .transpose()?;
+    STATE.save(deps.storage, &state)?;
 
-    Ok(res)
+    let collection = match &state.badge_collection {
+        Some(collection) => collection.to_string(),
+        None => "none".to_string(),
+    };
+    Ok(Response::new()
+        .add_attribute("action", "set_badge_collection")
+        .add_attribute("collection", collection))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetResult { host, opponent } => {
-            to_json_binary(&query_result(deps, host, opponent)?)
-        }
-    }
+pub fn try_reseed(
+    deps: DepsMut,
+    info: MessageInfo,
+    prng_seed: String,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+    RANDOM.save(deps.storage, &seed_random(&prng_seed))?;
+
+    Ok(Response::new().add_attribute("action", "reseed"))
 }
 
-pub fn query_result(deps: Deps, host: String, opponent: String) -> StdResult<ResultResponse> {
-    // validate host address
-    let validated_host = deps.api.addr_validate(&host)?;
-    let validated_opponent = deps.api.addr_validate(&opponent)?;
+pub fn try_update_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    action: cw_ownable::Action,
+) -> Result<Response, ContractError> {
+    let ownership = cw_ownable::update_ownership(deps, &env.block, &info.sender, action)?;
 
-    // load game map
-    let game = GAME.may_load(deps.storage, (validated_host, validated_opponent))?;
+    Ok(Response::new()
+        .add_attribute("action", "update_ownership")
+        .add_attributes(ownership.into_attributes()))
+}
 
-    // get game result if its exist otherwise throw error
-    match game {
-        Some(game) => match game.result {
-            Some(result) => Ok(ResultResponse { result }),
-            None => Err(StdError::not_found("The game still has no winner")),
-        },
-        None => Err(StdError::not_found("Host has not started a game")),
+fn validate_house_edge(house_edge_bps: u16) -> Result<(), ContractError> {
+    match house_edge_bps > TOTAL_BPS {
+        true => Err(ContractError::InvalidHouseEdge {}),
+        false => Ok(()),
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::state::GameMove;
-
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies_with_balance, mock_env, mock_info};
-    use cosmwasm_std::{coins, Addr};
+pub fn try_set_house_edge(
+    deps: DepsMut,
+    info: MessageInfo,
+    house_edge_bps: u16,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let mut state = STATE.load(deps.storage)?;
+    validate_house_edge(house_edge_bps)?;
 
-    #[test]
-    fn start_game() {
-        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+    state.house_edge_bps = house_edge_bps;
+    STATE.save(deps.storage, &state)?;
 
-        let info = mock_info("creator", &coins(1000, "earth"));
-        let msg = ExecuteMsg::StartGame {
-            opponent: "opponent".to_string(),
-            first_move: GameMove::Paper {},
-        };
+    Ok(Response::new()
+        .add_attribute("action", "set_house_edge")
+        .add_attribute("house_edge_bps", house_edge_bps.to_string()))
+}
 
-        // try to start game
-        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+pub fn try_withdraw(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    coin: Coin,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
-        // load game map
-        let game = GAME
-            .load(
-                &deps.storage,
-                (info.sender.clone(), Addr::unchecked("opponent")),
-            )
-            .unwrap();
+    let contract_addr = env.contract.address.clone();
+    let amount = coin.amount;
+    let denom = coin.denom.clone();
+    // escrowed stakes and the worst case of pending bets stay in the contract
+    validate_house_reserve(&deps, &contract_addr, &denom, amount)?;
 
-        assert_eq!(info.sender, game.host);
-        assert_eq!(GameMove::Paper {}, game.host_move);
-        assert_eq!(Addr::unchecked("opponent"), game.opponent);
-        assert_eq!(None, game.opp_move);
-        assert_eq!(None, game.result);
+    if amount.is_zero() {
+        return Err(ContractError::NoFunds {});
+    }
+
+    let res = Response::new()
+        .add_message(send_coin(&info.sender, coin)?)
+        .add_attribute("action", "withdraw")
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("denom", denom);
+
+    Ok(res)
+}
+
+pub fn try_bet_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    opp_move: GameMove,
+    entropy: String,
+    kind: Option<GameKind>,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage)?;
+    let kind = kind.unwrap_or_default();
+    kind.validate_move(&opp_move)?;
+
+    let fund = validate_sent_funds(info.funds)?;
+    let contract_addr = env.contract.address.clone();
+
+    // only whitelisted denoms can be bet, within the limits the owner set
+    let limit = BET_LIMITS
+        .may_load(deps.storage, &fund.denom)?
+        .ok_or_else(|| ContractError::DenomNotAllowed {
+            denom: fund.denom.clone(),
+        })?;
+    if fund.amount < limit.min_bet {
+        return Err(ContractError::BetTooSmall {
+            min_bet: limit.min_bet,
+        });
+    }
+    if fund.amount > limit.max_bet {
+        return Err(ContractError::BetTooLarge {
+            max_bet: limit.max_bet,
+        });
+    }
+
+    // the house has to be able to pay the worst case, a player win
+    let state = STATE.load(deps.storage)?;
+    let max_payout = bet_payout(fund.amount, &GameResult::PlayerWins(), state.house_edge_bps)?;
+    validate_house_reserve(&deps, &contract_addr, &fund.denom, max_payout)?;
+
+    if state.beacon_pubkey.is_some() {
+        // the house move is drawn from a beacon round nobody knows yet, a lagging relayer
+        // must not let bets wait on a round whose signature is already out
+        let round = LATEST_ROUND.may_load(deps.storage)?.unwrap_or_default() + 1;
+        let schedule = BEACON_SCHEDULE.load(deps.storage)?;
+        if round_time(&schedule, round) <= env.block.time {
+            return Err(ContractError::BeaconRoundPublished { round });
+        }
+        // the worst case payout stays reserved until the bet is settled
+        let id = next_bet_id(deps.storage)?;
+        reserve_funds(deps.storage, &Coin::new(max_payout, fund.denom.clone()))?;
+        let bet = PendingBet {
+            id,
+            player: info.sender.clone(),
+            player_move: opp_move,
+            kind,
+            bet: fund,
+            round,
+            house_edge_bps: state.house_edge_bps,
+        };
+        PENDING_BETS.save(deps.storage, id, &bet)?;
+
+        return Ok(Response::new()
+            .add_attribute("action", "bet_token")
+            .add_attribute("bet_id", id.to_string())
+            .add_attribute("round", round.to_string()));
+    }
+
+    let opponent = info.sender.clone();
+    let mut rand_state = RANDOM.load(deps.storage)?;
+    let rng = Prng::new_rand_bytes(&rand_state.entropy, (&entropy).as_ref());
+    rand_state.entropy = rng.clone();
+    RANDOM.save(deps.storage, &rand_state)?;
+
+    let contract_move = kind.rand_move(&rng);
+    let result = get_bet_result(kind, &opp_move, &contract_move);
+    let message = settle_house_bet(deps.storage, &opponent, fund, &result, state.house_edge_bps)?;
+
+    if let Some(msg) = message {
+        Ok(Response::new()
+            .add_message(msg)
+            .add_attribute("action", "bet_token")
+            .add_attribute("result", result.to_string()))
+    } else {
+        Ok(Response::new()
+            .add_attribute("action", "bet_token")
+            .add_attribute("result", result.to_string()))
+    }
+}
+
+pub fn get_bet_result(
+    kind: GameKind,
+    player_move: &GameMove,
+    contract_move: &GameMove,
+) -> GameResult {
+    match kind.resolve(player_move, contract_move) {
+        Ordering::Greater => GameResult::PlayerWins(),
+        Ordering::Less => GameResult::ContractWins(),
+        Ordering::Equal => GameResult::Tie {},
+    }
+}
+
+/// A player win returns the bet with the winnings minus the house edge,
+/// a tie returns the bet and a loss pays nothing
+pub fn bet_payout(bet: Uint128, result: &GameResult, house_edge_bps: u16) -> StdResult<Uint128> {
+    match result {
+        GameResult::PlayerWins() => {
+            let edge = bet.multiply_ratio(house_edge_bps, TOTAL_BPS);
+            Ok(bet.checked_add(bet - edge)?)
+        }
+        GameResult::Tie {} => Ok(bet),
+        _ => Ok(Uint128::zero()),
+    }
+}
+
+/// Books a settled house bet and pays the player
+fn settle_house_bet(
+    storage: &mut dyn Storage,
+    player: &Addr,
+    bet: Coin,
+    result: &GameResult,
+    house_edge_bps: u16,
+) -> StdResult<Option<CosmosMsg>> {
+    let payout = bet_payout(bet.amount, result, house_edge_bps)?;
+    record_house_bet(storage, &bet, payout)?;
+
+    match payout.is_zero() {
+        true => Ok(None),
+        false => send_coin(player, Coin::new(payout, bet.denom)).map(Some),
+    }
+}
+
+pub fn try_submit_beacon(
+    deps: DepsMut,
+    env: Env,
+    round: u64,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    let pubkey = state
+        .beacon_pubkey
+        .ok_or(ContractError::BeaconDisabled {})?;
+
+    // the beacon publishes rounds on schedule, an early round was signed ahead of time
+    let schedule = BEACON_SCHEDULE.load(deps.storage)?;
+    if round_time(&schedule, round) > env.block.time {
+        return Err(ContractError::BeaconRoundNotDue { round });
+    }
+
+    // pending bets wait on the next round, it cannot be skipped while they do
+    let expected = LATEST_ROUND.may_load(deps.storage)?.unwrap_or_default() + 1;
+    let waiting = PENDING_BETS
+        .range(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .is_some_and(|(_, bet)| bet.round == expected);
+    if round < expected || (waiting && round != expected) {
+        return Err(ContractError::UnexpectedBeaconRound { expected });
+    }
+
+    let randomness = verify_round(deps.api, &pubkey, round, &signature)?;
+    BEACON_ROUNDS.save(deps.storage, round, &randomness)?;
+    LATEST_ROUND.save(deps.storage, &round)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "submit_beacon")
+        .add_attribute("round", round.to_string())
+        .add_attribute("randomness", randomness.to_base64()))
+}
+
+pub fn try_settle_bet(deps: DepsMut, bet_id: u64) -> Result<Response, ContractError> {
+    let bet = PENDING_BETS
+        .may_load(deps.storage, bet_id)?
+        .ok_or(ContractError::BetNotFound {})?;
+    let randomness = BEACON_ROUNDS
+        .may_load(deps.storage, bet.round)?
+        .ok_or(ContractError::BeaconRoundMissing { round: bet.round })?;
+
+    let contract_move = bet.kind.rand_move(&bet_randomness(&randomness, bet.id));
+    let result = get_bet_result(bet.kind, &bet.player_move, &contract_move);
+
+    let max_payout = bet_payout(
+        bet.bet.amount,
+        &GameResult::PlayerWins(),
+        bet.house_edge_bps,
+    )?;
+    release_funds(deps.storage, &Coin::new(max_payout, bet.bet.denom.clone()))?;
+    PENDING_BETS.remove(deps.storage, bet_id);
+
+    let res = Response::new()
+        .add_attribute("action", "settle_bet")
+        .add_attribute("bet_id", bet_id.to_string())
+        .add_attribute("contract_move", contract_move.to_string())
+        .add_attribute("result", result.to_string());
+
+    match settle_house_bet(
+        deps.storage,
+        &bet.player,
+        bet.bet,
+        &result,
+        bet.house_edge_bps,
+    )? {
+        Some(msg) => Ok(res.add_message(msg)),
+        None => Ok(res),
+    }
+}
+
+pub fn try_set_beacon(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    pubkey: Option<Binary>,
+    schedule: Option<BeaconSchedule>,
+) -> Result<Response, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let mut state = STATE.load(deps.storage)?;
+
+    // pending bets could never be settled without a beacon, and a new key
+    // could sign the rounds they wait on with outcomes of its choosing
+    if !PENDING_BETS.is_empty(deps.storage) {
+        return Err(ContractError::BetsPending {});
+    }
+    if let Some(pubkey) = &pubkey {
+        validate_pubkey(deps.api, pubkey)?;
+        // rounds keep their schedule when the key is rotated, unless a new one is given
+        let schedule = match schedule {
+            Some(schedule) => Some(schedule),
+            None if BEACON_SCHEDULE.exists(deps.storage) => None,
+            None => Some(default_schedule(env.block.time)),
+        };
+        if let Some(schedule) = schedule {
+            validate_schedule(&schedule)?;
+            BEACON_SCHEDULE.save(deps.storage, &schedule)?;
+        }
+    }
+    state.beacon_pubkey = pubkey;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_beacon")
+        .add_attribute("enabled", state.beacon_pubkey.is_some().to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn try_respond_to_game(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    host: String,
+    second_move: GameMove,
+    game_id: Option<u64>,
+    nft: Option<Nft>,
+) -> Result<Response, ContractError> {
+    // validate host address
+    let host_addr = deps.api.addr_validate(&host)?;
+    let responder_addr = info.sender;
+
+    //load game by passing host addr and opponent addr, or take it from the lobby
+    let mut game = match game_id {
+        Some(id) if challenges().has(deps.storage, id) => {
+            accept_challenge(deps.storage, id, &host_addr, &responder_addr)?
+        }
+        _ => load_game(deps.storage, &host_addr, &responder_addr, game_id)?,
+    };
+
+    if game.opponent != responder_addr {
+        return Err(ContractError::UnauthorizedOpponent {});
+    }
+    if game.opp_move.is_some() {
+        return Err(ContractError::AlreadyResponded {});
+    }
+    game.kind.validate_move(&second_move)?;
+
+    // opponent has to match the host stake exactly, once for the whole match
+    match &game.stake {
+        Some(stake) if game.round == 1 => {
+            if validate_sent_funds(info.funds)? != *stake {
+                return Err(ContractError::StakeMismatch {});
+            }
+            reserve_funds(deps.storage, stake)?;
+        }
+        _ => {
+            if !info.funds.is_empty() {
+                return Err(ContractError::StakeMismatch {});
+            }
+        }
+    }
+    // an NFT stake is matched once by a token of any accepted collection
+    let nft_expected = game.round == 1 && game.host_nft.is_some();
+    if nft_expected != nft.is_some() {
+        return Err(ContractError::NftStakeMismatch {});
+    }
+    if nft_expected {
+        game.opp_nft = nft;
+    }
+
+    if game.expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+
+    // the host move stays hidden behind its commitment until the host reveals it,
+    // from now on the host has to reveal before the refreshed deadline
+    game.opp_move = Some(second_move.clone());
+    game.expires = game.timeout.after(&env.block);
+    active_games().save(deps.storage, game.id, &game)?;
+
+    let res = Response::new()
+        .add_attribute("action", "respond")
+        .add_attribute("host", host_addr)
+        .add_attribute("opponent", responder_addr)
+        .add_attribute("opp_move", second_move.to_string());
+
+    Ok(res)
+}
+
+/// Takes an open challenge off the lobby, turning it into a game against `opponent`
+fn accept_challenge(
+    storage: &mut dyn Storage,
+    id: u64,
+    host: &Addr,
+    opponent: &Addr,
+) -> Result<Game, ContractError> {
+    assert_not_paused(storage)?;
+    let challenge = challenges()
+        .may_load(storage, id)?
+        .filter(|challenge| challenge.host == *host)
+        .ok_or(ContractError::GameNotFound {})?;
+    if host == opponent {
+        return Err(ContractError::SelfPlay {});
+    }
+    challenges().remove(storage, id)?;
+
+    Ok(challenge.into_game(opponent.clone()))
+}
+
+pub fn try_cancel_challenge(
+    deps: DepsMut,
+    info: MessageInfo,
+    game_id: u64,
+) -> Result<Response, ContractError> {
+    let challenge = challenges()
+        .may_load(deps.storage, game_id)?
+        .ok_or(ContractError::GameNotFound {})?;
+    if challenge.host != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    challenges().remove(deps.storage, game_id)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "cancel_challenge")
+        .add_attribute("game_id", game_id.to_string())
+        .add_attribute("host", challenge.host.clone());
+    if let Some(stake) = challenge.stake {
+        res = res.add_message(pay_out(deps.storage, &challenge.host, stake)?);
+    }
+    if let Some(nft) = challenge.host_nft {
+        res = res.add_message(transfer_nft(&challenge.host, nft)?);
+    }
+
+    Ok(res)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn try_reveal(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    opponent: String,
+    first_move: GameMove,
+    salt: String,
+    next_commitment: Option<Binary>,
+    game_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    // validate opponent address
+    let opponent_addr = deps.api.addr_validate(&opponent)?;
+    let host_addr = info.sender;
+
+    let mut game = load_game(deps.storage, &host_addr, &opponent_addr, game_id)?;
+
+    if game.opp_move.is_none() {
+        return Err(ContractError::NotResponded {});
+    }
+    if game.expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    if game.circuit.is_some() {
+        return Err(ContractError::ProofRequired {});
+    }
+    if commit_move(&first_move, &salt) != game.host_commitment.as_slice() {
+        return Err(ContractError::InvalidReveal {});
+    }
+    game.kind.validate_move(&first_move)?;
+
+    game.host_move = Some(first_move);
+    let round_result = get_game_result(game.clone()).unwrap();
+
+    finish_round(deps.storage, &env, game, round_result, next_commitment)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn try_prove_outcome(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    opponent: String,
+    result: GameResult,
+    proof: Binary,
+    next_commitment: Option<Binary>,
+    game_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let opponent_addr = deps.api.addr_validate(&opponent)?;
+    let host_addr = info.sender;
+
+    let game = load_game(deps.storage, &host_addr, &opponent_addr, game_id)?;
+
+    let opp_move = game
+        .opp_move
+        .clone()
+        .ok_or(ContractError::NotResponded {})?;
+    if game.expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    let circuit = game
+        .circuit
+        .clone()
+        .ok_or(ContractError::RevealRequired {})?;
+    let vk = VERIFYING_KEYS.load(deps.storage, &circuit)?;
+
+    // the host move stays hidden, the proof binds the commitment to the outcome
+    verify_outcome(&vk, &proof, &game.host_commitment, &opp_move, &result)?;
+
+    finish_round(deps.storage, &env, game, result, next_commitment)
+}
+
+/// Scores a played round. The game is settled once a player has won the majority
+/// of the rounds, otherwise the next round starts with the host's new commitment.
+fn finish_round(
+    storage: &mut dyn Storage,
+    env: &Env,
+    mut game: Game,
+    round_result: GameResult,
+    next_commitment: Option<Binary>,
+) -> Result<Response, ContractError> {
+    match round_result {
+        GameResult::HostWins {} => game.host_score += 1,
+        GameResult::OpponentWins {} => game.opp_score += 1,
+        // a single throw ends in a tie, a series or a bracket match replays the round
+        _ if game.rounds == 1 && game.tournament.is_none() => {
+            game.result = Some(round_result);
+            return settle_game(storage, env, game);
+        }
+        _ => {}
+    }
+
+    let majority = game.rounds / 2 + 1;
+    if game.host_score == majority {
+        game.result = Some(GameResult::HostWins {});
+        return settle_game(storage, env, game);
+    }
+    if game.opp_score == majority {
+        game.result = Some(GameResult::OpponentWins {});
+        return settle_game(storage, env, game);
+    }
+
+    let commitment = next_commitment.ok_or(ContractError::CommitmentRequired {})?;
+    validate_commitment(storage, &game.circuit, game.kind, &commitment)?;
+
+    let res = Response::new()
+        .add_attribute("action", "finish_round")
+        .add_attribute("game_id", game.id.to_string())
+        .add_attribute("round", game.round.to_string())
+        .add_attribute("round_result", round_result.to_string())
+        .add_attribute("host_score", game.host_score.to_string())
+        .add_attribute("opp_score", game.opp_score.to_string());
+
+    // the opponent plays first again, against the new commitment
+    game.round += 1;
+    game.host_commitment = commitment;
+    game.host_move = None;
+    game.opp_move = None;
+    game.expires = game.timeout.after(&env.block);
+    active_games().save(storage, game.id, &game)?;
+
+    Ok(res)
+}
+
+/// Pays out and archives a game whose result is known
+fn settle_game(
+    storage: &mut dyn Storage,
+    env: &Env,
+    game: Game,
+) -> Result<Response, ContractError> {
+    let result = game.result.clone().unwrap();
+    let payouts = stake_payouts(storage, &game, &result)?;
+    archive_game(storage, &game)?;
+    let mints = update_ratings(storage, &game)?;
+    let bracket = advance_bracket(storage, env, &game)?;
+    active_games().remove(storage, game.id)?;
+
+    let res: Response = ResultResponse { result }.into();
+    Ok(res
+        .add_attribute("game_id", game.id.to_string())
+        .add_messages(payouts)
+        .add_submessages(mints)
+        .add_submessages(bracket))
+}
+
+/// Pays the escrowed stakes out, the winner takes both and a tie refunds each player
+pub fn stake_payouts(
+    storage: &mut dyn Storage,
+    game: &Game,
+    result: &GameResult,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut messages = nft_payouts(game, result)?;
+    messages.extend(coin_payouts(storage, game, result)?);
+    Ok(messages)
+}
+
+fn coin_payouts(
+    storage: &mut dyn Storage,
+    game: &Game,
+    result: &GameResult,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut messages = vec![];
+    let stake = match &game.stake {
+        Some(stake) => stake,
+        None => return Ok(messages),
+    };
+
+    let pot = Coin {
+        denom: stake.denom.clone(),
+        amount: stake.amount.checked_add(stake.amount)?,
+    };
+    match result {
+        GameResult::HostWins {} => messages.push(pay_out(storage, &game.host, pot)?),
+        GameResult::OpponentWins {} => messages.push(pay_out(storage, &game.opponent, pot)?),
+        _ => {
+            messages.push(pay_out(storage, &game.host, stake.clone())?);
+            messages.push(pay_out(storage, &game.opponent, stake.clone())?);
+        }
+    }
+
+    Ok(messages)
+}
+
+/// Hands the escrowed NFTs out like coin stakes, the winner takes both tokens and a tie
+/// hands each token back
+fn nft_payouts(game: &Game, result: &GameResult) -> StdResult<Vec<CosmosMsg>> {
+    let mut messages = vec![];
+    for (owner, nft) in [
+        (&game.host, &game.host_nft),
+        (&game.opponent, &game.opp_nft),
+    ] {
+        if let Some(nft) = nft {
+            let to = match result {
+                GameResult::HostWins {} => &game.host,
+                GameResult::OpponentWins {} => &game.opponent,
+                _ => owner,
+            };
+            messages.push(transfer_nft(to, nft.clone())?);
+        }
+    }
+    Ok(messages)
+}
+
+/// Rates both players of a finished game and adds it to their records,
+/// returns the mints of the badges the game earned them
+fn update_ratings(storage: &mut dyn Storage, game: &Game) -> StdResult<Vec<SubMsg>> {
+    let mut host = player_stats(storage, &game.host)?;
+    let mut opponent = player_stats(storage, &game.opponent)?;
+
+    let score = match game.result {
+        Some(GameResult::HostWins {}) => {
+            host.wins += 1;
+            host.streak += 1;
+            opponent.losses += 1;
+            opponent.streak = 0;
+            WIN
+        }
+        Some(GameResult::OpponentWins {}) => {
+            host.losses += 1;
+            host.streak = 0;
+            opponent.wins += 1;
+            opponent.streak += 1;
+            LOSS
+        }
+        _ => {
+            host.ties += 1;
+            host.streak = 0;
+            opponent.ties += 1;
+            opponent.streak = 0;
+            TIE
+        }
+    };
+    let change = rating_change(host.rating, opponent.rating, score);
+    host.rating = apply_change(host.rating, change);
+    opponent.rating = apply_change(opponent.rating, -change);
+
+    players().save(storage, game.host.clone(), &host)?;
+    players().save(storage, game.opponent.clone(), &opponent)?;
+
+    let mut mints = vec![];
+    for stats in [host, opponent] {
+        for badge in game_badges(&stats) {
+            mints.extend(mint_badge(storage, &stats.address, &badge)?);
+        }
+    }
+    Ok(mints)
+}
+
+/// Sends escrowed funds to a player and releases them from the reserve
+fn pay_out(storage: &mut dyn Storage, to: &Addr, coin: Coin) -> StdResult<CosmosMsg> {
+    release_funds(storage, &coin)?;
+    send_coin(to, coin)
+}
+
+pub fn get_game_result(game: Game) -> Option<GameResult> {
+    let host_move = game.host_move?;
+    let opp_move = game.opp_move?;
+
+    match game.kind.resolve(&host_move, &opp_move) {
+        Ordering::Greater => Some(GameResult::HostWins {}),
+        Ordering::Less => Some(GameResult::OpponentWins {}),
+        Ordering::Equal => Some(GameResult::Tie {}),
+    }
+}
+
+pub fn try_cancel_game(
+    deps: DepsMut,
+    info: MessageInfo,
+    opponent: String,
+    game_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let opponent_addr = deps.api.addr_validate(&opponent)?;
+    let host_addr = info.sender;
+
+    let game = load_game(deps.storage, &host_addr, &opponent_addr, game_id)?;
+    if game.tournament.is_some() {
+        return Err(ContractError::TournamentGame {});
+    }
+    if game.opp_move.is_some() || game.round > 1 {
+        return Err(ContractError::AlreadyResponded {});
+    }
+    active_games().remove(deps.storage, game.id)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "cancel_game")
+        .add_attribute("game_id", game.id.to_string())
+        .add_attribute("host", host_addr.clone())
+        .add_attribute("opponent", opponent_addr);
+    if let Some(stake) = game.stake {
+        res = res.add_message(pay_out(deps.storage, &host_addr, stake)?);
+    }
+    if let Some(nft) = game.host_nft {
+        res = res.add_message(transfer_nft(&host_addr, nft)?);
+    }
+
+    Ok(res)
+}
+
+pub fn try_claim_timeout(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    host: String,
+    opponent: String,
+    game_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let host_addr = deps.api.addr_validate(&host)?;
+    let opponent_addr = deps.api.addr_validate(&opponent)?;
+    if info.sender != host_addr && info.sender != opponent_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut game = load_game(deps.storage, &host_addr, &opponent_addr, game_id)?;
+    if !game.expires.is_expired(&env.block) {
+        return Err(ContractError::GameNotExpired {});
+    }
+    active_games().remove(deps.storage, game.id)?;
+
+    let res = Response::new()
+        .add_attribute("action", "claim_timeout")
+        .add_attribute("host", host_addr.clone())
+        .add_attribute("opponent", opponent_addr);
+
+    let result = match game.opp_move {
+        // the opponent never played, nothing to award so the host stake goes back
+        None if game.round == 1 && game.tournament.is_none() => {
+            let mut res = res.add_attribute("game_status", "expired");
+            if let Some(stake) = game.stake {
+                res = res.add_message(pay_out(deps.storage, &host_addr, stake)?);
+            }
+            if let Some(nft) = game.host_nft {
+                res = res.add_message(transfer_nft(&host_addr, nft)?);
+            }
+            return Ok(res);
+        }
+        // the opponent left a series in progress and forfeits it
+        None => GameResult::HostWins {},
+        // the host did not reveal in time and forfeits the game
+        Some(_) => GameResult::OpponentWins {},
+    };
+    game.result = Some(result.clone());
+    // the forfeit settles the coin stakes only, each NFT goes back to its owner
+    let mut payouts = nft_payouts(&game, &GameResult::Tie {})?;
+    payouts.extend(coin_payouts(deps.storage, &game, &result)?);
+    archive_game(deps.storage, &game)?;
+    let mints = update_ratings(deps.storage, &game)?;
+    let bracket = advance_bracket(deps.storage, &env, &game)?;
+    Ok(res
+        .add_attribute("game_id", game.id.to_string())
+        .add_attribute("game_status", "forfeited")
+        .add_attribute("Result", result.to_string())
+        .add_messages(payouts)
+        .add_submessages(mints)
+        .add_submessages(bracket))
+}
+
+pub fn try_create_tournament(
+    deps: DepsMut,
+    info: MessageInfo,
+    entry_fee: Coin,
+    max_players: u32,
+    organizer_cut_bps: Option<u16>,
+    prize_split: Option<Vec<u16>>,
+    timeout: Option<Duration>,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage)?;
+    let organizer_cut_bps = organizer_cut_bps.unwrap_or_default();
+    let prize_split = prize_split.unwrap_or(vec![TOTAL_BPS]);
+    validate_settings(max_players, organizer_cut_bps, &prize_split)?;
+    if entry_fee.amount.is_zero() {
+        return Err(ContractError::EntryFeeMismatch { entry_fee });
+    }
+    // a cw20 entry fee is paid through `Receive`, which only accepted tokens can call
+    if let Some(token) = cw20_address(&entry_fee.denom) {
+        if !ACCEPTED_CW20.has(deps.storage, &token) {
+            return Err(ContractError::Cw20NotAccepted {
+                token: token.to_string(),
+            });
+        }
+    }
+
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => STATE.load(deps.storage)?.game_timeout,
+    };
+    validate_timeout(&timeout)?;
+
+    let tournament = Tournament {
+        id: next_tournament_id(deps.storage)?,
+        organizer: info.sender,
+        entry_fee,
+        max_players,
+        organizer_cut_bps,
+        prize_split,
+        players: vec![],
+        status: TournamentStatus::Registration {},
+        round: 0,
+        timeout,
+        champion: None,
+    };
+    TOURNAMENTS.save(deps.storage, tournament.id, &tournament)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_tournament")
+        .add_attribute("tournament_id", tournament.id.to_string())
+        .add_attribute("organizer", tournament.organizer)
+        .add_attribute("entry_fee", tournament.entry_fee.to_string()))
+}
+
+pub fn try_register_tournament(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    tournament_id: u64,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage)?;
+    let mut tournament = TOURNAMENTS.load(deps.storage, tournament_id)?;
+    if tournament.status != (TournamentStatus::Registration {}) {
+        return Err(ContractError::RegistrationClosed {});
+    }
+    if tournament.players.contains(&info.sender) {
+        return Err(ContractError::AlreadyRegistered {});
+    }
+    if validate_sent_funds(info.funds)? != tournament.entry_fee {
+        return Err(ContractError::EntryFeeMismatch {
+            entry_fee: tournament.entry_fee,
+        });
+    }
+
+    // entry fees are held for the prize pool
+    reserve_funds(deps.storage, &tournament.entry_fee)?;
+    tournament.players.push(info.sender.clone());
+
+    let mut res = Response::new()
+        .add_attribute("action", "register_tournament")
+        .add_attribute("tournament_id", tournament_id.to_string())
+        .add_attribute("player", info.sender);
+
+    // a full bracket starts right away
+    if tournament.players.len() as u32 == tournament.max_players {
+        let players = tournament.players.clone();
+        start_bracket_round(deps.storage, &env, &mut tournament, players)?;
+        res = res.add_attribute("round", tournament.round.to_string());
+    }
+    TOURNAMENTS.save(deps.storage, tournament_id, &tournament)?;
+
+    Ok(res)
+}
+
+pub fn try_start_tournament(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    tournament_id: u64,
+) -> Result<Response, ContractError> {
+    let mut tournament = TOURNAMENTS.load(deps.storage, tournament_id)?;
+    if tournament.organizer != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if tournament.status != (TournamentStatus::Registration {}) {
+        return Err(ContractError::RegistrationClosed {});
+    }
+    if tournament.players.len() < 2 {
+        return Err(ContractError::NotEnoughPlayers {});
+    }
+
+    let players = tournament.players.clone();
+    start_bracket_round(deps.storage, &env, &mut tournament, players)?;
+    TOURNAMENTS.save(deps.storage, tournament_id, &tournament)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "start_tournament")
+        .add_attribute("tournament_id", tournament_id.to_string())
+        .add_attribute("players", tournament.players.len().to_string()))
+}
+
+pub fn try_cancel_tournament(
+    deps: DepsMut,
+    info: MessageInfo,
+    tournament_id: u64,
+) -> Result<Response, ContractError> {
+    let mut tournament = TOURNAMENTS.load(deps.storage, tournament_id)?;
+    if tournament.organizer != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if tournament.status != (TournamentStatus::Registration {}) {
+        return Err(ContractError::RegistrationClosed {});
+    }
+
+    tournament.status = TournamentStatus::Cancelled {};
+    TOURNAMENTS.save(deps.storage, tournament_id, &tournament)?;
+
+    let refunds = tournament
+        .players
+        .iter()
+        .map(|player| pay_out(deps.storage, player, tournament.entry_fee.clone()))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_tournament")
+        .add_attribute("tournament_id", tournament_id.to_string())
+        .add_messages(refunds))
+}
+
+pub fn try_start_match(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    tournament_id: u64,
+    commitment: Binary,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage)?;
+    let tournament = TOURNAMENTS.load(deps.storage, tournament_id)?;
+    let mut bracket_match = round_matches(deps.storage, tournament_id, tournament.round)?
+        .into_iter()
+        .find(|m| m.player1 == info.sender && m.winner.is_none() && m.game_id.is_none())
+        .ok_or(ContractError::MatchNotFound {})?;
+    // byes are decided when the round starts
+    let opponent = bracket_match.player2.clone().unwrap();
+
+    if bracket_match.expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    validate_commitment(deps.storage, &None, GameKind::Rps, &commitment)?;
+
+    let game = Game {
+        id: next_game_id(deps.storage)?,
+        host: info.sender.clone(),
+        opponent: opponent.clone(),
+        host_commitment: commitment,
+        circuit: None,
+        // bracket games are always classic rock paper scissors
+        kind: GameKind::Rps,
+        host_move: None,
+        opp_move: None,
+        result: None,
+        rounds: 1,
+        round: 1,
+        host_score: 0,
+        opp_score: 0,
+        stake: None,
+        host_nft: None,
+        opp_nft: None,
+        timeout: tournament.timeout,
+        expires: tournament.timeout.after(&env.block),
+        tournament: Some(MatchRef {
+            tournament_id,
+            round: bracket_match.round,
+            slot: bracket_match.slot,
+        }),
+    };
+    active_games().save(deps.storage, game.id, &game)?;
+
+    bracket_match.game_id = Some(game.id);
+    TOURNAMENT_MATCHES.save(
+        deps.storage,
+        (tournament_id, bracket_match.round, bracket_match.slot),
+        &bracket_match,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "start_match")
+        .add_attribute("tournament_id", tournament_id.to_string())
+        .add_attribute("round", bracket_match.round.to_string())
+        .add_attribute("game_id", game.id.to_string())
+        .add_attribute("host", info.sender)
+        .add_attribute("opponent", opponent))
+}
+
+pub fn try_claim_match(
+    deps: DepsMut,
+    env: Env,
+    tournament_id: u64,
+    slot: u32,
+) -> Result<Response, ContractError> {
+    let tournament = TOURNAMENTS.load(deps.storage, tournament_id)?;
+    let bracket_match = TOURNAMENT_MATCHES
+        .may_load(deps.storage, (tournament_id, tournament.round, slot))?
+        .filter(|m| m.winner.is_none())
+        .ok_or(ContractError::MatchNotFound {})?;
+    if bracket_match.game_id.is_some() {
+        return Err(ContractError::AlreadyStarted {});
+    }
+    if !bracket_match.expires.is_expired(&env.block) {
+        return Err(ContractError::GameNotExpired {});
+    }
+
+    // the first player never showed up
+    let winner = bracket_match.player2.unwrap();
+    let match_ref = MatchRef {
+        tournament_id,
+        round: tournament.round,
+        slot,
+    };
+    let payouts = record_match_winner(deps.storage, &env, &match_ref, &winner)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "claim_match")
+        .add_attribute("tournament_id", tournament_id.to_string())
+        .add_attribute("winner", winner)
+        .add_submessages(payouts))
+}
+
+/// Pairs `players` for the next round of the bracket, byes advance right away
+fn start_bracket_round(
+    storage: &mut dyn Storage,
+    env: &Env,
+    tournament: &mut Tournament,
+    players: Vec<Addr>,
+) -> StdResult<()> {
+    tournament.status = TournamentStatus::InProgress {};
+    tournament.round += 1;
+
+    for (slot, (player1, player2)) in pair_players(&players).into_iter().enumerate() {
+        let winner = match player2 {
+            Some(_) => None,
+            None => Some(player1.clone()),
+        };
+        let bracket_match = TournamentMatch {
+            round: tournament.round,
+            slot: slot as u32,
+            player1,
+            player2,
+            game_id: None,
+            winner,
+            expires: tournament.timeout.after(&env.block),
+        };
+        TOURNAMENT_MATCHES.save(
+            storage,
+            (tournament.id, tournament.round, slot as u32),
+            &bracket_match,
+        )?;
+    }
+    Ok(())
+}
+
+/// Moves the winner of a finished tournament game up the bracket
+fn advance_bracket(storage: &mut dyn Storage, env: &Env, game: &Game) -> StdResult<Vec<SubMsg>> {
+    let match_ref = match &game.tournament {
+        Some(match_ref) => match_ref,
+        None => return Ok(vec![]),
+    };
+    // bracket games are replayed until there is a winner
+    let winner = match game.result {
+        Some(GameResult::HostWins {}) => &game.host,
+        _ => &game.opponent,
+    };
+    record_match_winner(storage, env, match_ref, winner)
+}
+
+/// Decides a bracket match. Once all matches of the round are decided the winners
+/// are paired for the next round, or the last one standing is the champion and
+/// the prize pool is paid out.
+fn record_match_winner(
+    storage: &mut dyn Storage,
+    env: &Env,
+    match_ref: &MatchRef,
+    winner: &Addr,
+) -> StdResult<Vec<SubMsg>> {
+    let key = (match_ref.tournament_id, match_ref.round, match_ref.slot);
+    let mut bracket_match = TOURNAMENT_MATCHES.load(storage, key)?;
+    bracket_match.winner = Some(winner.clone());
+    TOURNAMENT_MATCHES.save(storage, key, &bracket_match)?;
+
+    let mut tournament = TOURNAMENTS.load(storage, match_ref.tournament_id)?;
+    let matches = round_matches(storage, tournament.id, tournament.round)?;
+    let winners = matches
+        .iter()
+        .map(|m| m.winner.clone())
+        .collect::<Option<Vec<_>>>();
+
+    let payouts = match winners {
+        // the round is still being played
+        None => vec![],
+        Some(winners) if winners.len() > 1 => {
+            start_bracket_round(storage, env, &mut tournament, winners)?;
+            vec![]
+        }
+        Some(_) => finish_tournament(storage, &mut tournament, winner)?,
+    };
+    TOURNAMENTS.save(storage, tournament.id, &tournament)?;
+
+    Ok(payouts)
+}
+
+fn round_matches(
+    storage: &dyn Storage,
+    tournament_id: u64,
+    round: u32,
+) -> StdResult<Vec<TournamentMatch>> {
+    TOURNAMENT_MATCHES
+        .prefix((tournament_id, round))
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, m)| m))
+        .collect()
+}
+
+fn finish_tournament(
+    storage: &mut dyn Storage,
+    tournament: &mut Tournament,
+    champion: &Addr,
+) -> StdResult<Vec<SubMsg>> {
+    tournament.status = TournamentStatus::Finished {};
+    tournament.champion = Some(champion.clone());
+
+    // losers of the final and of the semifinals, a bye loses nobody
+    let losers = |matches: Vec<TournamentMatch>| -> Vec<Addr> {
+        matches
+            .into_iter()
+            .filter_map(|m| match m.winner == Some(m.player1.clone()) {
+                true => m.player2,
+                false => Some(m.player1),
+            })
+            .collect()
+    };
+    let finalist = losers(round_matches(storage, tournament.id, tournament.round)?);
+    let semifinalists = match tournament.round {
+        1 => vec![],
+        round => losers(round_matches(storage, tournament.id, round - 1)?),
+    };
+    let places = [vec![champion.clone()], finalist, semifinalists];
+
+    let pot = tournament
+        .entry_fee
+        .amount
+        .checked_mul(Uint128::from(tournament.players.len() as u128))?;
+    let (cut, prizes) = prize_payouts(
+        pot,
+        tournament.organizer_cut_bps,
+        &tournament.prize_split,
+        &places,
+    );
+
+    let denom = tournament.entry_fee.denom.clone();
+    let champion_badge = Badge::TournamentChampion {
+        tournament_id: tournament.id,
+    };
+    let mut payouts: Vec<SubMsg> = mint_badge(storage, champion, &champion_badge)?
+        .into_iter()
+        .collect();
+    if !cut.is_zero() {
+        payouts.push(SubMsg::new(pay_out(
+            storage,
+            &tournament.organizer,
+            Coin::new(cut, denom.clone()),
+        )?));
+    }
+    for (player, amount) in prizes {
+        if !amount.is_zero() {
+            let coin = Coin::new(amount, denom.clone());
+            payouts.push(SubMsg::new(pay_out(storage, &player, coin)?));
+        }
+    }
+    Ok(payouts)
+}
+
+fn validate_timeout(timeout: &Duration) -> Result<(), ContractError> {
+    match timeout {
+        Duration::Height(0) | Duration::Time(0) => Err(ContractError::InvalidTimeout {}),
+        _ => Ok(()),
+    }
+}
+
+const MAX_ROUNDS: u32 = 7;
+
+fn validate_rounds(rounds: u32) -> Result<(), ContractError> {
+    // an even series could end with a drawn score
+    match rounds % 2 == 1 && rounds <= MAX_ROUNDS {
+        true => Ok(()),
+        false => Err(ContractError::InvalidRounds {
+            max_rounds: MAX_ROUNDS,
+        }),
+    }
+}
+
+/// Checks a host commitment for the game kind, a MiMC field element for circuit games
+fn validate_commitment(
+    storage: &dyn Storage,
+    circuit: &Option<String>,
+    kind: GameKind,
+    commitment: &Binary,
+) -> Result<(), ContractError> {
+    match circuit {
+        Some(circuit) => {
+            // the outcome circuit only encodes the three classic moves
+            if kind != GameKind::Rps {
+                return Err(ContractError::UnsupportedGameKind {});
+            }
+            if !VERIFYING_KEYS.has(storage, circuit) {
+                return Err(ContractError::UnknownCircuit {
+                    circuit: circuit.clone(),
+                });
+            }
+            field_from_bytes(commitment)?;
+        }
+        None => {
+            if commitment.len() != SHA256_HASH_SIZE {
+                return Err(ContractError::InvalidCommitment {});
+            }
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn try_start_game(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    opponent: Option<String>,
+    commitment: Binary,
+    timeout: Option<Duration>,
+    circuit: Option<String>,
+    rounds: Option<u32>,
+    kind: Option<GameKind>,
+    nft: Option<Nft>,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage)?;
+    // validate opponent address
+    let opponent_addr = opponent
+        .map(|opponent| deps.api.addr_validate(&opponent))
+        .transpose()?;
+    if opponent_addr.as_ref() == Some(&info.sender) {
+        return Err(ContractError::SelfPlay {});
+    }
+
+    let kind = kind.unwrap_or_default();
+    validate_commitment(deps.storage, &circuit, kind, &commitment)?;
+    let rounds = rounds.unwrap_or(1);
+    validate_rounds(rounds)?;
+
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => STATE.load(deps.storage)?.game_timeout,
+    };
+    validate_timeout(&timeout)?;
+
+    // funds sent along are escrowed until the game is settled
+    let stake = if info.funds.is_empty() {
+        None
+    } else {
+        let stake = validate_sent_funds(info.funds.clone())?;
+        reserve_funds(deps.storage, &stake)?;
+        Some(stake)
+    };
+
+    let id = next_game_id(deps.storage)?;
+    let challenge = Challenge {
+        id,
+        host: info.sender.clone(),
+        host_commitment: commitment.clone(),
+        circuit,
+        kind,
+        rounds,
+        stake: stake.clone(),
+        host_nft: nft.clone(),
+        timeout,
+        expires: timeout.after(&env.block),
+    };
+
+    let mut res = Response::new()
+        .add_attribute("action", "start_game")
+        .add_attribute("game_id", id.to_string())
+        .add_attribute("host", info.sender.clone());
+
+    match opponent_addr {
+        Some(opponent_addr) => {
+            // a host can run several games against the same opponent, each under its id
+            let game = challenge.into_game(opponent_addr.clone());
+            active_games().save(deps.storage, id, &game)?;
+            res = res.add_attribute("opponent", opponent_addr);
+        }
+        // anyone can accept an open challenge from the lobby
+        None => {
+            challenges().save(deps.storage, id, &challenge)?;
+            res = res.add_attribute("opponent", "open");
+        }
+    }
+
+    res = res
+        .add_attribute("commitment", commitment.to_base64())
+        .add_attribute("kind", kind.to_string())
+        .add_attribute("rounds", rounds.to_string());
+    if let Some(stake) = stake {
+        res = res.add_attribute("stake", stake.to_string());
+    }
+    if let Some(nft) = nft {
+        res = res.add_attribute("nft", nft.to_string());
+    }
+
+    Ok(res)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    let res = match msg {
+        QueryMsg::GetResult { host, opponent } => {
+            to_json_binary(&query_result(deps, host, opponent)?)
+        }
+        QueryMsg::GamesByPlayer {
+            player,
+            start_after,
+            limit,
+        } => to_json_binary(&query_games_by_player(deps, player, start_after, limit)?),
+        QueryMsg::RecentGames { start_after, limit } => {
+            to_json_binary(&query_recent_games(deps, start_after, limit)?)
+        }
+        QueryMsg::BetLimits {} => to_json_binary(&query_bet_limits(deps)?),
+        QueryMsg::Beacon {} => to_json_binary(&query_beacon(deps)?),
+        QueryMsg::PendingBet { bet_id } => {
+            to_json_binary(&PENDING_BETS.load(deps.storage, bet_id)?)
+        }
+        QueryMsg::Circuits {} => to_json_binary(&query_circuits(deps)?),
+        QueryMsg::OpenChallenges {
+            denom,
+            amount,
+            start_after,
+            limit,
+        } => to_json_binary(&query_open_challenges(
+            deps,
+            env,
+            denom,
+            amount,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::PlayerStats { address } => {
+            let address = deps.api.addr_validate(&address)?;
+            to_json_binary(&player_stats(deps.storage, &address)?)
+        }
+        QueryMsg::Leaderboard { start_after, limit } => {
+            to_json_binary(&query_leaderboard(deps, start_after, limit)?)
+        }
+        QueryMsg::Tournament { tournament_id } => {
+            to_json_binary(&query_tournament(deps, tournament_id)?)
+        }
+        QueryMsg::Tournaments { start_after, limit } => {
+            to_json_binary(&query_tournaments(deps, start_after, limit)?)
+        }
+        QueryMsg::Cw20Tokens {} => to_json_binary(&query_cw20_tokens(deps)?),
+        QueryMsg::Collections {} => to_json_binary(&query_collections(deps)?),
+        QueryMsg::HouseStats {} => to_json_binary(&query_house_stats(deps)?),
+        QueryMsg::Paused {} => to_json_binary(&PausedResponse {
+            paused: STATE.load(deps.storage)?.paused,
+        }),
+        QueryMsg::BadgeCollection {} => to_json_binary(&BadgeCollectionResponse {
+            collection: STATE.load(deps.storage)?.badge_collection,
+        }),
+        QueryMsg::Ownership {} => to_json_binary(&cw_ownable::get_ownership(deps.storage)?),
+    };
+    Ok(res?)
+}
+
+pub fn query_house_stats(deps: Deps) -> StdResult<HouseStatsResponse> {
+    let state = STATE.load(deps.storage)?;
+    let stats = HOUSE_STATS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (denom, stats) = item?;
+            let net_profit = Int128::try_from(stats.wagered)? - Int128::try_from(stats.paid_out)?;
+            let reserved = RESERVED.may_load(deps.storage, &denom)?.unwrap_or_default();
+            Ok(DenomStatsResponse {
+                denom,
+                wagered: stats.wagered,
+                paid_out: stats.paid_out,
+                net_profit,
+                reserved,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(HouseStatsResponse {
+        house_edge_bps: state.house_edge_bps,
+        stats,
+    })
+}
+
+pub fn query_cw20_tokens(deps: Deps) -> StdResult<Cw20TokensResponse> {
+    let tokens = ACCEPTED_CW20
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(Cw20TokensResponse { tokens })
+}
+
+pub fn query_collections(deps: Deps) -> StdResult<CollectionsResponse> {
+    let collections = ACCEPTED_CW721
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(CollectionsResponse { collections })
+}
+
+pub fn query_tournament(deps: Deps, tournament_id: u64) -> StdResult<TournamentResponse> {
+    let tournament = TOURNAMENTS.load(deps.storage, tournament_id)?;
+    let matches = TOURNAMENT_MATCHES
+        .sub_prefix(tournament_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, m)| m))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(TournamentResponse {
+        tournament,
+        matches,
+    })
+}
+
+pub fn query_tournaments(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<TournamentsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let end = start_after.map(Bound::exclusive);
+
+    let tournaments = TOURNAMENTS
+        .range(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|item| item.map(|(_, tournament)| tournament))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(TournamentsResponse { tournaments })
+}
+
+pub fn query_leaderboard(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<LeaderboardResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // the index is ordered by rating and then address, the page continues below the given player
+    let end = match start_after {
+        Some(address) => {
+            let address = deps.api.addr_validate(&address)?;
+            let stats = players().load(deps.storage, address.clone())?;
+            Some(Bound::exclusive((stats.rating, address)))
+        }
+        None => None,
+    };
+
+    let players = players()
+        .idx
+        .rating
+        .range(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|item| item.map(|(_, stats)| stats))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(LeaderboardResponse { players })
+}
+
+pub fn query_open_challenges(
+    deps: Deps,
+    env: Env,
+    denom: Option<String>,
+    amount: Option<Uint128>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<OpenChallengesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let unstaked = denom.as_deref() == Some("");
+
+    let lobby = challenges();
+    let range = match (denom, amount) {
+        (Some(denom), Some(amount)) => lobby.idx.stake.prefix((denom, amount.u128())).range(
+            deps.storage,
+            start,
+            None,
+            Order::Ascending,
+        ),
+        (Some(denom), None) => {
+            lobby
+                .idx
+                .denom
+                .prefix(denom)
+                .range(deps.storage, start, None, Order::Ascending)
+        }
+        (None, Some(_)) => return Err(StdError::generic_err("amount filter needs a denom")),
+        (None, None) => lobby.range(deps.storage, start, None, Order::Ascending),
+    };
+    let challenges = range
+        .map(|item| item.map(|(_, challenge)| challenge))
+        .filter(|item| match item {
+            // NFT stakes share the empty denom of the unstaked challenges in the indexes
+            Ok(challenge) if unstaked && challenge.host_nft.is_some() => false,
+            // expired challenges can no longer be accepted
+            Ok(challenge) => !challenge.expires.is_expired(&env.block),
+            Err(_) => true,
+        })
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(OpenChallengesResponse { challenges })
+}
+
+pub fn query_circuits(deps: Deps) -> StdResult<CircuitsResponse> {
+    let circuits = VERIFYING_KEYS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(CircuitsResponse { circuits })
+}
+
+pub fn query_beacon(deps: Deps) -> StdResult<BeaconResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(BeaconResponse {
+        pubkey: state.beacon_pubkey,
+        latest_round: LATEST_ROUND.may_load(deps.storage)?.unwrap_or_default(),
+        schedule: BEACON_SCHEDULE.may_load(deps.storage)?,
+    })
+}
+
+pub fn query_bet_limits(deps: Deps) -> StdResult<BetLimitsResponse> {
+    let limits = BET_LIMITS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(denom, limit)| BetLimitResponse {
+                denom,
+                min_bet: limit.min_bet,
+                max_bet: limit.max_bet,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(BetLimitsResponse { limits })
+}
+
+pub fn query_result(
+    deps: Deps,
+    host: String,
+    opponent: String,
+) -> Result<ResultResponse, ContractError> {
+    // validate host address
+    let validated_host = deps.api.addr_validate(&host)?;
+    let validated_opponent = deps.api.addr_validate(&opponent)?;
+
+    // finished games are archived, the active one has no result yet
+    if let Some(id) = LAST_GAME.may_load(
+        deps.storage,
+        (validated_host.clone(), validated_opponent.clone()),
+    )? {
+        if let Some(result) = GAMES.load(deps.storage, id)?.result {
+            return Ok(ResultResponse { result });
+        }
+    }
+
+    let running = active_games()
+        .idx
+        .pair
+        .prefix((validated_host, validated_opponent))
+        .keys(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    match running {
+        true => Err(ContractError::GameNotFinished {}),
+        false => Err(ContractError::GameNotFound {}),
+    }
+}
+
+pub fn query_games_by_player(
+    deps: Deps,
+    player: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<GameResponse> {
+    let player = deps.api.addr_validate(&player)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let end = start_after.map(Bound::exclusive);
+
+    let result = PLAYER_GAMES
+        .prefix(player)
+        .keys(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|id| GAMES.load(deps.storage, id?))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(GameResponse { result })
+}
+
+pub fn query_recent_games(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<GameResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let end = start_after.map(Bound::exclusive);
+
+    let result = GAMES
+        .range(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|item| item.map(|(_, game)| game))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(GameResponse { result })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::badges::Metadata;
+    use crate::state::{GameMove, PlayerStats, RESERVED};
+    use cosmwasm_std::WasmMsg;
+    use cw721_base::msg::ExecuteMsg as Cw721ExecuteMsg;
+    use cw721_base::Extension;
+    use cw_ownable::OwnershipError;
+    use cw_utils::PaymentError;
+
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies_with_balance, mock_env, mock_info, MockApi};
+    use cosmwasm_std::{coin, coins, from_json, Addr, BankMsg, CosmosMsg};
+
+    const SALT: &str = "pepper";
+
+    fn commitment(game_move: &GameMove) -> Binary {
+        Binary::from(commit_move(game_move, SALT))
+    }
+
+    fn setup(deps: DepsMut) {
+        let owner = MockApi::default().addr_make("owner");
+        let msg = InstantiateMsg {
+            owner: owner.clone(),
+            prng_seed: "seed".to_string(),
+            game_timeout: None,
+            beacon_pubkey: None,
+            beacon_schedule: None,
+            verifying_keys: None,
+            house_edge_bps: None,
+            badge_collection: None,
+        };
+        instantiate(deps, mock_env(), mock_info(owner.as_ref(), &[]), msg).unwrap();
+    }
+
+    /// Starts a game of `host` committed to `game_move` with deadlines of 10 blocks,
+    /// an open challenge without an `opponent`
+    fn new_game(
+        deps: DepsMut,
+        host: &Addr,
+        opponent: Option<&Addr>,
+        game_move: GameMove,
+        funds: &[Coin],
+        rounds: Option<u32>,
+        kind: Option<GameKind>,
+    ) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::StartGame {
+            opponent: opponent.map(Addr::to_string),
+            commitment: commitment(&game_move),
+            timeout: Some(Duration::Height(10)),
+            circuit: None,
+            rounds,
+            kind,
+        };
+        execute(deps, mock_env(), mock_info(host.as_ref(), funds), msg)
+    }
+
+    #[test]
+    fn start_game() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let creator = deps.api.addr_make("creator");
+        let opponent = deps.api.addr_make("opponent");
+
+        // try to start game
+        let res = new_game(
+            deps.as_mut(),
+            &creator,
+            Some(&opponent),
+            GameMove::Paper {},
+            &coins(1000, "earth"),
+            None,
+            None,
+        )
+        .unwrap();
+
+        // the move itself must not leak through the events
+        assert!(res.attributes.iter().all(|attr| attr.key != "host_move"));
+
+        // load game map
+        let game = load_game(&deps.storage, &creator, &opponent, None).unwrap();
+
+        assert_eq!(creator, game.host);
+        assert_eq!(commitment(&GameMove::Paper {}), game.host_commitment);
+        assert_eq!(None, game.host_move);
+        assert_eq!(opponent, game.opponent);
+        assert_eq!(None, game.opp_move);
+        assert_eq!(None, game.result);
+        assert_eq!(Some(coin(1000, "earth")), game.stake);
+    }
+
+    #[test]
+    fn start_game_with_same_host_diff_opponent() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let creator = deps.api.addr_make("creator");
+        let opponent = deps.api.addr_make("opponent");
+        let opponent2 = deps.api.addr_make("opponent2");
+
+        for opponent in [&opponent, &opponent2] {
+            new_game(
+                deps.as_mut(),
+                &creator,
+                Some(opponent),
+                GameMove::Paper {},
+                &coins(1000, "earth"),
+                None,
+                None,
+            )
+            .unwrap();
+        }
+
+        // load games map
+        let game1 = load_game(&deps.storage, &creator, &opponent, None).unwrap();
+        let game2 = load_game(&deps.storage, &creator, &opponent2, None).unwrap();
+
+        assert_eq!(creator, game1.host);
+        assert_eq!(commitment(&GameMove::Paper {}), game1.host_commitment);
+        assert_eq!(opponent, game1.opponent);
+        assert_eq!(None, game1.opp_move);
+        assert_eq!(None, game1.result);
+
+        assert_eq!(creator, game2.host);
+        assert_eq!(commitment(&GameMove::Paper {}), game2.host_commitment);
+        assert_eq!(opponent2, game2.opponent);
+        assert_eq!(None, game2.opp_move);
+        assert_eq!(None, game2.result);
+    }
+
+    #[test]
+    fn commit_respond_reveal() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Rock {},
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+
+        // the host cannot reveal before the opponent played
+        let reveal = ExecuteMsg::Reveal {
+            opponent: opponent.to_string(),
+            first_move: GameMove::Rock {},
+            salt: SALT.to_string(),
+            next_commitment: None,
+            game_id: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            reveal.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotResponded {}));
+
+        let msg = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: GameMove::Scissors {},
+            game_id: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &[]),
+            msg.clone(),
+        )
+        .unwrap();
+
+        // the opponent cannot change its move
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyResponded {}));
+
+        let game = load_game(&deps.storage, &host, &opponent, None).unwrap();
+        assert_eq!(Some(GameMove::Scissors {}), game.opp_move);
+        assert_eq!(None, game.host_move);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            reveal,
+        )
+        .unwrap();
+        assert_eq!(res.attributes[1].value, GameResult::HostWins {}.to_string());
+    }
+
+    #[test]
+    fn reveal_must_match_commitment() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Rock {},
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: GameMove::Paper {},
+            game_id: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+
+        // host tries to switch to the winning move
+        let msg = ExecuteMsg::Reveal {
+            opponent: opponent.to_string(),
+            first_move: GameMove::Scissors {},
+            salt: SALT.to_string(),
+            next_commitment: None,
+            game_id: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidReveal {}));
+
+        // wrong salt is rejected as well
+        let msg = ExecuteMsg::Reveal {
+            opponent: opponent.to_string(),
+            first_move: GameMove::Rock {},
+            salt: "salt".to_string(),
+            next_commitment: None,
+            game_id: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidReveal {}));
+    }
+
+    #[test]
+    fn staked_game_pays_winner() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Paper {},
+            &coins(100, "earth"),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let respond = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: GameMove::Rock {},
+            game_id: None,
+        };
+
+        // opponent has to match the stake
+        for funds in [coins(99, "earth"), coins(100, "mars")] {
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(opponent.as_ref(), &funds),
+                respond.clone(),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::StakeMismatch {}));
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &[]),
+            respond.clone(),
+        )
+        .unwrap_err();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &coins(100, "earth")),
+            respond,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Reveal {
+            opponent: opponent.to_string(),
+            first_move: GameMove::Paper {},
+            salt: SALT.to_string(),
+            next_commitment: None,
+            game_id: None,
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages
+                .iter()
+                .map(|m| m.msg.clone())
+                .collect::<Vec<_>>(),
+            vec![CosmosMsg::Bank(BankMsg::Send {
+                to_address: host.to_string(),
+                amount: coins(200, "earth"),
+            })]
+        );
+    }
+
+    #[test]
+    fn staked_game_refunds_tie() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Scissors {},
+            &coins(100, "earth"),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: GameMove::Scissors {},
+            game_id: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &coins(100, "earth")),
+            msg,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Reveal {
+            opponent: opponent.to_string(),
+            first_move: GameMove::Scissors {},
+            salt: SALT.to_string(),
+            next_commitment: None,
+            game_id: None,
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages
+                .iter()
+                .map(|m| m.msg.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: host.to_string(),
+                    amount: coins(100, "earth"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: opponent.to_string(),
+                    amount: coins(100, "earth"),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn unstaked_game_rejects_funds() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Scissors {},
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: GameMove::Rock {},
+            game_id: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &coins(100, "earth")),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::StakeMismatch {}));
+    }
+
+    #[test]
+    fn cancel_game_before_response() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Rock {},
+            &coins(100, "earth"),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::CancelGame {
+            opponent: opponent.to_string(),
+            game_id: None,
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: host.to_string(),
+                amount: coins(100, "earth"),
+            })
+        );
+        assert!(load_game(&deps.storage, &host, &opponent, None).is_err());
+
+        // a new game against the same opponent can be started right away
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Rock {},
+            &coins(100, "earth"),
+            None,
+            None,
+        )
+        .unwrap();
+        let msg = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: GameMove::Rock {},
+            game_id: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &coins(100, "earth")),
+            msg,
+        )
+        .unwrap();
+
+        // but it cannot be cancelled once the opponent played
+        let msg = ExecuteMsg::CancelGame {
+            opponent: opponent.to_string(),
+            game_id: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyResponded {}));
+    }
+
+    #[test]
+    fn pair_plays_several_staked_games() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Paper {},
+            &coins(100, "earth"),
+            None,
+            None,
+        )
+        .unwrap();
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Rock {},
+            &coins(100, "earth"),
+            None,
+            None,
+        )
+        .unwrap();
+
+        // with two games running every move has to name its game
+        let respond_msg = |game_id| ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: GameMove::Rock {},
+            game_id,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &coins(100, "earth")),
+            respond_msg(None),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::GameIdRequired {}));
+        for game_id in [1, 2] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(opponent.as_ref(), &coins(100, "earth")),
+                respond_msg(Some(game_id)),
+            )
+            .unwrap();
+        }
+
+        let reveal_msg = |first_move, game_id| ExecuteMsg::Reveal {
+            opponent: opponent.to_string(),
+            first_move,
+            salt: SALT.to_string(),
+            next_commitment: None,
+            game_id,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            reveal_msg(GameMove::Paper {}, None),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::GameIdRequired {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            reveal_msg(GameMove::Paper {}, Some(3)),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::GameNotFound {}));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            reveal_msg(GameMove::Paper {}, Some(1)),
+        )
+        .unwrap();
+        assert_eq!("Host Wins!", res.attributes[1].value);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: host.to_string(),
+                amount: coins(200, "earth"),
+            })
+        );
+
+        // the last game running needs no id
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            reveal_msg(GameMove::Rock {}, None),
+        )
+        .unwrap();
+        assert_eq!("Game is Tie !", res.attributes[1].value);
+        assert_eq!(
+            vec![Some(GameResult::Tie {}), Some(GameResult::HostWins {})],
+            query_recent_games(deps.as_ref(), None, None)
+                .unwrap()
+                .result
+                .into_iter()
+                .map(|game| game.result)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn claim_timeout_without_response_refunds_host() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Rock {},
+            &coins(100, "earth"),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::ClaimTimeout {
+            host: host.to_string(),
+            opponent: opponent.to_string(),
+            game_id: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::GameNotExpired {}));
+
+        let mut env = mock_env();
+        env.block.height += 10;
+
+        // the opponent cannot respond anymore
+        let respond = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: GameMove::Paper {},
+            game_id: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(opponent.as_ref(), &coins(100, "earth")),
+            respond,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Expired {}));
+
+        // strangers cannot settle the game
+        let stranger = deps.api.addr_make("stranger");
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(stranger.as_ref(), &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(deps.as_mut(), env, mock_info(host.as_ref(), &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: host.to_string(),
+                amount: coins(100, "earth"),
+            })
+        );
+        assert!(load_game(&deps.storage, &host, &opponent, None).is_err());
+    }
+
+    #[test]
+    fn claim_timeout_without_reveal_awards_opponent() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Rock {},
+            &coins(100, "earth"),
+            None,
+            None,
+        )
+        .unwrap();
+
+        // the response refreshes the deadline for the host
+        let mut env = mock_env();
+        env.block.height += 5;
+        let msg = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: GameMove::Scissors {},
+            game_id: None,
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(opponent.as_ref(), &coins(100, "earth")),
+            msg,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::ClaimTimeout {
+            host: host.to_string(),
+            opponent: opponent.to_string(),
+            game_id: None,
+        };
+        env.block.height += 5;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(opponent.as_ref(), &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::GameNotExpired {}));
+
+        // the host is too late to reveal its winning move
+        env.block.height += 5;
+        let reveal = ExecuteMsg::Reveal {
+            opponent: opponent.to_string(),
+            first_move: GameMove::Rock {},
+            salt: SALT.to_string(),
+            next_commitment: None,
+            game_id: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(host.as_ref(), &[]),
+            reveal,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Expired {}));
+
+        let res = execute(deps.as_mut(), env, mock_info(opponent.as_ref(), &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: opponent.to_string(),
+                amount: coins(200, "earth"),
+            })
+        );
+    }
+
+    fn play_game(
+        mut deps: DepsMut,
+        host: &Addr,
+        opponent: &Addr,
+        first: GameMove,
+        second: GameMove,
+    ) {
+        new_game(
+            deps.branch(),
+            host,
+            Some(opponent),
+            first.clone(),
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: second,
+            game_id: None,
+        };
+        execute(
+            deps.branch(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Reveal {
+            opponent: opponent.to_string(),
+            first_move: first,
+            salt: SALT.to_string(),
+            next_commitment: None,
+            game_id: None,
+        };
+        execute(deps, mock_env(), mock_info(host.as_ref(), &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn finished_games_are_archived() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let alice = deps.api.addr_make("alice");
+        let bob = deps.api.addr_make("bob");
+        let carol = deps.api.addr_make("carol");
+
+        let msg = QueryMsg::GetResult {
+            host: alice.to_string(),
+            opponent: bob.to_string(),
+        };
+        let err = query(deps.as_ref(), mock_env(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::GameNotFound {}));
+
+        play_game(
+            deps.as_mut(),
+            &alice,
+            &bob,
+            GameMove::Rock {},
+            GameMove::Paper {},
+        );
+        play_game(
+            deps.as_mut(),
+            &bob,
+            &carol,
+            GameMove::Rock {},
+            GameMove::Rock {},
+        );
+        play_game(
+            deps.as_mut(),
+            &alice,
+            &carol,
+            GameMove::Paper {},
+            GameMove::Rock {},
+        );
+
+        let res: ResultResponse =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(GameResult::OpponentWins {}, res.result);
+
+        // a rematch does not hide the last result
+        play_game(
+            deps.as_mut(),
+            &alice,
+            &bob,
+            GameMove::Rock {},
+            GameMove::Scissors {},
+        );
+        let msg = QueryMsg::GetResult {
+            host: alice.to_string(),
+            opponent: bob.to_string(),
+        };
+        let res: ResultResponse =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(GameResult::HostWins {}, res.result);
+
+        let msg = QueryMsg::GamesByPlayer {
+            player: alice.to_string(),
+            start_after: None,
+            limit: Some(2),
+        };
+        let res: GameResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(
+            vec![4, 3],
+            res.result.iter().map(|g| g.id).collect::<Vec<_>>()
+        );
+
+        let msg = QueryMsg::GamesByPlayer {
+            player: alice.to_string(),
+            start_after: Some(3),
+            limit: None,
+        };
+        let res: GameResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(vec![1], res.result.iter().map(|g| g.id).collect::<Vec<_>>());
+        assert_eq!(Some(GameMove::Rock {}), res.result[0].host_move);
+        assert_eq!(Some(GameResult::OpponentWins {}), res.result[0].result);
+
+        let msg = QueryMsg::RecentGames {
+            start_after: Some(4),
+            limit: Some(2),
+        };
+        let res: GameResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(
+            vec![3, 2],
+            res.result.iter().map(|g| g.id).collect::<Vec<_>>()
+        );
+    }
+
+    fn respond(deps: DepsMut, host: &Addr, opponent: &Addr, second: GameMove, funds: &[Coin]) {
+        let msg = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: second,
+            game_id: None,
+        };
+        execute(deps, mock_env(), mock_info(opponent.as_ref(), funds), msg).unwrap();
+    }
+
+    fn reveal(
+        deps: DepsMut,
+        host: &Addr,
+        opponent: &Addr,
+        first: GameMove,
+        next: Option<GameMove>,
+    ) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::Reveal {
+            opponent: opponent.to_string(),
+            first_move: first,
+            salt: SALT.to_string(),
+            next_commitment: next.as_ref().map(commitment),
+            game_id: None,
+        };
+        execute(deps, mock_env(), mock_info(host.as_ref(), &[]), msg)
+    }
+
+    #[test]
+    fn best_of_three_replays_ties() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+
+        // a series needs an odd number of rounds
+        for rounds in [2, 9] {
+            let err = new_game(
+                deps.as_mut(),
+                &host,
+                Some(&opponent),
+                GameMove::Rock {},
+                &[],
+                Some(rounds),
+                None,
+            )
+            .unwrap_err();
+            assert!(matches!(
+                err,
+                ContractError::InvalidRounds { max_rounds: 7 }
+            ));
+        }
+
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Rock {},
+            &coins(100, "earth"),
+            Some(3),
+            None,
+        )
+        .unwrap();
+
+        // round 1 goes to the host
+        respond(
+            deps.as_mut(),
+            &host,
+            &opponent,
+            GameMove::Scissors {},
+            &coins(100, "earth"),
+        );
+        let err = reveal(deps.as_mut(), &host, &opponent, GameMove::Rock {}, None).unwrap_err();
+        assert!(matches!(err, ContractError::CommitmentRequired {}));
+        let res = reveal(
+            deps.as_mut(),
+            &host,
+            &opponent,
+            GameMove::Rock {},
+            Some(GameMove::Paper {}),
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(GameResult::HostWins {}.to_string(), res.attributes[3].value);
+
+        // the stake is matched once for the whole series
+        let msg = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: GameMove::Paper {},
+            game_id: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &coins(100, "earth")),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::StakeMismatch {}));
+
+        // a tie does not count and is replayed
+        respond(deps.as_mut(), &host, &opponent, GameMove::Paper {}, &[]);
+        reveal(
+            deps.as_mut(),
+            &host,
+            &opponent,
+            GameMove::Paper {},
+            Some(GameMove::Rock {}),
+        )
+        .unwrap();
+
+        respond(deps.as_mut(), &host, &opponent, GameMove::Paper {}, &[]);
+        reveal(
+            deps.as_mut(),
+            &host,
+            &opponent,
+            GameMove::Rock {},
+            Some(GameMove::Scissors {}),
+        )
+        .unwrap();
+
+        let game = load_game(&deps.storage, &host, &opponent, None).unwrap();
+        assert_eq!((4, 1, 1), (game.round, game.host_score, game.opp_score));
+        assert_eq!(commitment(&GameMove::Scissors {}), game.host_commitment);
+        assert_eq!(None, game.result);
+
+        // the series cannot be called off once it is under way
+        let msg = ExecuteMsg::CancelGame {
+            opponent: opponent.to_string(),
+            game_id: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyResponded {}));
+
+        respond(deps.as_mut(), &host, &opponent, GameMove::Paper {}, &[]);
+        let res = reveal(deps.as_mut(), &host, &opponent, GameMove::Scissors {}, None).unwrap();
+        assert_eq!(res.attributes[1].value, GameResult::HostWins {}.to_string());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: host.to_string(),
+                amount: coins(200, "earth"),
+            })
+        );
+        assert!(load_game(&deps.storage, &host, &opponent, None).is_err());
+    }
+
+    #[test]
+    fn abandoned_series_is_forfeited() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Rock {},
+            &coins(100, "earth"),
+            Some(5),
+            None,
+        )
+        .unwrap();
+
+        respond(
+            deps.as_mut(),
+            &host,
+            &opponent,
+            GameMove::Rock {},
+            &coins(100, "earth"),
+        );
+        reveal(
+            deps.as_mut(),
+            &host,
+            &opponent,
+            GameMove::Rock {},
+            Some(GameMove::Paper {}),
+        )
+        .unwrap();
+
+        // the opponent stops playing after the first round
+        let mut env = mock_env();
+        env.block.height += 11;
+        let msg = ExecuteMsg::ClaimTimeout {
+            host: host.to_string(),
+            opponent: opponent.to_string(),
+            game_id: None,
+        };
+        let res = execute(deps.as_mut(), env, mock_info(host.as_ref(), &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: host.to_string(),
+                amount: coins(200, "earth"),
+            })
+        );
+    }
+
+    fn lobby_ids(deps: Deps, denom: Option<&str>, amount: Option<u128>) -> Vec<u64> {
+        let msg = QueryMsg::OpenChallenges {
+            denom: denom.map(String::from),
+            amount: amount.map(Uint128::new),
+            start_after: None,
+            limit: None,
+        };
+        let res: OpenChallengesResponse = from_json(query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.challenges.into_iter().map(|c| c.id).collect()
+    }
+
+    #[test]
+    fn open_challenges_lobby() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let host = deps.api.addr_make("host");
+        let host2 = deps.api.addr_make("host2");
+        let opponent = deps.api.addr_make("opponent");
+
+        new_game(
+            deps.as_mut(),
+            &host,
+            None,
+            GameMove::Rock {},
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+        new_game(
+            deps.as_mut(),
+            &host2,
+            None,
+            GameMove::Rock {},
+            &coins(100, "earth"),
+            None,
+            None,
+        )
+        .unwrap();
+        new_game(
+            deps.as_mut(),
+            &host2,
+            None,
+            GameMove::Rock {},
+            &coins(50, "earth"),
+            None,
+            None,
+        )
+        .unwrap();
+        new_game(
+            deps.as_mut(),
+            &host,
+            None,
+            GameMove::Rock {},
+            &coins(100, "moon"),
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(vec![1, 2, 3, 4], lobby_ids(deps.as_ref(), None, None));
+        assert_eq!(vec![2, 3], lobby_ids(deps.as_ref(), Some("earth"), None));
+        assert_eq!(vec![2], lobby_ids(deps.as_ref(), Some("earth"), Some(100)));
+        assert_eq!(vec![1], lobby_ids(deps.as_ref(), Some(""), None));
+
+        let msg = QueryMsg::OpenChallenges {
+            denom: None,
+            amount: None,
+            start_after: Some(2),
+            limit: Some(1),
+        };
+        let res: OpenChallengesResponse =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(3, res.challenges[0].id);
+        assert_eq!(1, res.challenges.len());
+
+        // the id has to belong to the given host
+        let msg = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: GameMove::Paper {},
+            game_id: Some(2),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &coins(100, "earth")),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::GameNotFound {}));
+
+        // accepting a challenge turns it into a regular game
+        let msg = ExecuteMsg::Respond {
+            host: host2.to_string(),
+            second_move: GameMove::Paper {},
+            game_id: Some(2),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &coins(100, "earth")),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(vec![3], lobby_ids(deps.as_ref(), Some("earth"), None));
+
+        let game = load_game(&deps.storage, &host2, &opponent, None).unwrap();
+        assert_eq!(2, game.id);
+        assert_eq!(Some(GameMove::Paper {}), game.opp_move);
+
+        let res = reveal(deps.as_mut(), &host2, &opponent, GameMove::Rock {}, None).unwrap();
+        assert_eq!(
+            res.attributes[1].value,
+            GameResult::OpponentWins {}.to_string()
+        );
+
+        // only the host can take its challenge back
+        let msg = ExecuteMsg::CancelChallenge { game_id: 3 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host2.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: host2.to_string(),
+                amount: coins(50, "earth"),
+            })
+        );
+        assert_eq!(vec![1, 4], lobby_ids(deps.as_ref(), None, None));
+
+        // an amount alone does not filter anything
+        let msg = QueryMsg::OpenChallenges {
+            denom: None,
+            amount: Some(Uint128::new(100)),
+            start_after: None,
+            limit: None,
+        };
+        query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    }
+
+    #[test]
+    fn expired_challenges_leave_the_lobby() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let host = deps.api.addr_make("host");
+
+        new_game(
+            deps.as_mut(),
+            &host,
+            None,
+            GameMove::Rock {},
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+        // a challenge open for longer than the helper ones
+        let msg = ExecuteMsg::StartGame {
+            opponent: None,
+            commitment: commitment(&GameMove::Paper {}),
+            timeout: Some(Duration::Height(20)),
+            circuit: None,
+            rounds: None,
+            kind: None,
+        };
+        let host2 = deps.api.addr_make("host2");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host2.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(vec![1, 2], lobby_ids(deps.as_ref(), None, None));
+
+        // the first challenge expires before the second one
+        let mut env = mock_env();
+        env.block.height += 10;
+        let msg = QueryMsg::OpenChallenges {
+            denom: Some(String::new()),
+            amount: None,
+            start_after: None,
+            limit: Some(1),
+        };
+        let res: OpenChallengesResponse =
+            from_json(query(deps.as_ref(), env, msg).unwrap()).unwrap();
+        assert_eq!(1, res.challenges.len());
+        assert_eq!(2, res.challenges[0].id);
+    }
+
+    #[test]
+    fn ratings_and_leaderboard() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let alice = deps.api.addr_make("alice");
+        let bob = deps.api.addr_make("bob");
+        let carol = deps.api.addr_make("carol");
+
+        play_game(
+            deps.as_mut(),
+            &alice,
+            &bob,
+            GameMove::Rock {},
+            GameMove::Scissors {},
+        );
+        // the opponent is rated the same way as the host
+        play_game(
+            deps.as_mut(),
+            &carol,
+            &alice,
+            GameMove::Rock {},
+            GameMove::Paper {},
+        );
+        play_game(
+            deps.as_mut(),
+            &bob,
+            &carol,
+            GameMove::Paper {},
+            GameMove::Paper {},
+        );
+
+        let stats: PlayerStats = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::PlayerStats {
+                    address: alice.to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            PlayerStats {
+                address: alice.clone(),
+                rating: 1232,
+                wins: 2,
+                losses: 0,
+                ties: 0,
+                streak: 2,
+            },
+            stats
+        );
+
+        // players without games are unrated
+        let dave = deps.api.addr_make("dave");
+        let stats: PlayerStats = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::PlayerStats {
+                    address: dave.to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            (1200, 0),
+            (stats.rating, stats.wins + stats.losses + stats.ties)
+        );
+
+        let res: LeaderboardResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Leaderboard {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let ranking: Vec<_> = res.players.iter().map(|p| (p.rating, p.ties)).collect();
+        assert_eq!(vec![(1232, 0), (1184, 1), (1184, 1)], ranking);
+        assert_eq!(alice, res.players[0].address);
+
+        // players with the same rating are paged by address
+        let res: LeaderboardResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Leaderboard {
+                    start_after: Some(res.players[1].address.to_string()),
+                    limit: Some(1),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(1, res.players.len());
+        assert_eq!(1184, res.players[0].rating);
+        assert_ne!(alice, res.players[0].address);
+    }
+
+    fn register(deps: DepsMut, player: &Addr, funds: &[Coin]) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::RegisterTournament { tournament_id: 1 };
+        execute(deps, mock_env(), mock_info(player.as_ref(), funds), msg)
+    }
+
+    #[test]
+    fn tournament_bracket() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let organizer = deps.api.addr_make("organizer");
+        let players: Vec<Addr> = ["p1", "p2", "p3"]
+            .iter()
+            .map(|name| deps.api.addr_make(name))
+            .collect();
+        let (p1, p2, p3) = (&players[0], &players[1], &players[2]);
+
+        let msg = ExecuteMsg::CreateTournament {
+            entry_fee: coin(100, "earth"),
+            max_players: 4,
+            organizer_cut_bps: Some(1_000),
+            prize_split: Some(vec![7_000, 2_000]),
+            timeout: Some(Duration::Height(10)),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(organizer.as_ref(), &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPrizeSplit {}));
+
+        let msg = ExecuteMsg::CreateTournament {
+            entry_fee: coin(100, "earth"),
+            max_players: 4,
+            organizer_cut_bps: Some(1_000),
+            prize_split: Some(vec![7_000, 3_000]),
+            timeout: Some(Duration::Height(10)),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(organizer.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+
+        let err = register(deps.as_mut(), p1, &coins(50, "earth")).unwrap_err();
+        assert!(matches!(err, ContractError::EntryFeeMismatch { .. }));
+        for player in &players {
+            register(deps.as_mut(), player, &coins(100, "earth")).unwrap();
+        }
+        let err = register(deps.as_mut(), p1, &coins(100, "earth")).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyRegistered {}));
+
+        // three players, the third one gets a bye
+        let msg = ExecuteMsg::StartTournament { tournament_id: 1 };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(organizer.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+        let err = register(deps.as_mut(), &organizer, &coins(100, "earth")).unwrap_err();
+        assert!(matches!(err, ContractError::RegistrationClosed {}));
+
+        // the first player of the pairing hosts the game
+        let start_match = |game_move: GameMove| ExecuteMsg::StartMatch {
+            tournament_id: 1,
+            commitment: commitment(&game_move),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(p2.as_ref(), &[]),
+            start_match(GameMove::Rock {}),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MatchNotFound {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(p1.as_ref(), &[]),
+            start_match(GameMove::Rock {}),
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::CancelGame {
+            opponent: p2.to_string(),
+            game_id: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(p1.as_ref(), &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::TournamentGame {}));
+
+        // a tie is replayed
+        respond(deps.as_mut(), p1, p2, GameMove::Rock {}, &[]);
+        reveal(
+            deps.as_mut(),
+            p1,
+            p2,
+            GameMove::Rock {},
+            Some(GameMove::Paper {}),
+        )
+        .unwrap();
+        respond(deps.as_mut(), p1, p2, GameMove::Rock {}, &[]);
+        reveal(deps.as_mut(), p1, p2, GameMove::Paper {}, None).unwrap();
+
+        // the final is forfeited by its host
+        let msg = ExecuteMsg::ClaimMatch {
+            tournament_id: 1,
+            slot: 0,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(p3.as_ref(), &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::GameNotExpired {}));
+
+        let mut env = mock_env();
+        env.block.height += 11;
+        let res = execute(deps.as_mut(), env, mock_info(p3.as_ref(), &[]), msg).unwrap();
+        let payouts: Vec<_> = res.messages.into_iter().map(|m| m.msg).collect();
+        assert_eq!(
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: organizer.to_string(),
+                    amount: coins(30, "earth"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: p3.to_string(),
+                    amount: coins(189, "earth"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: p1.to_string(),
+                    amount: coins(81, "earth"),
+                }),
+            ],
+            payouts
+        );
+
+        let res: TournamentResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Tournament { tournament_id: 1 },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(TournamentStatus::Finished {}, res.tournament.status);
+        assert_eq!(Some(p3.clone()), res.tournament.champion);
+        let bracket: Vec<_> = res
+            .matches
+            .iter()
+            .map(|m| (m.round, m.slot, m.winner.clone()))
+            .collect();
+        assert_eq!(
+            vec![
+                (1, 0, Some(p1.clone())),
+                (1, 1, Some(p3.clone())),
+                (2, 0, Some(p3.clone())),
+            ],
+            bracket
+        );
+        assert!(RESERVED.load(&deps.storage, "earth").unwrap().is_zero());
+    }
+
+    #[test]
+    fn match_next_to_a_casual_game() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let organizer = deps.api.addr_make("organizer");
+        let p1 = deps.api.addr_make("p1");
+        let p2 = deps.api.addr_make("p2");
+
+        // the pair already plays a casual game
+        new_game(
+            deps.as_mut(),
+            &p1,
+            Some(&p2),
+            GameMove::Rock {},
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::CreateTournament {
+            entry_fee: coin(100, "earth"),
+            max_players: 2,
+            organizer_cut_bps: None,
+            prize_split: None,
+            timeout: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(organizer.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+        register(deps.as_mut(), &p1, &coins(100, "earth")).unwrap();
+        register(deps.as_mut(), &p2, &coins(100, "earth")).unwrap();
+
+        let msg = ExecuteMsg::StartMatch {
+            tournament_id: 1,
+            commitment: commitment(&GameMove::Rock {}),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(p1.as_ref(), &[]), msg).unwrap();
+        assert_eq!("2", res.attributes[3].value);
+
+        // both games are open, moves name the game
+        let respond = |game_id: Option<u64>| ExecuteMsg::Respond {
+            host: p1.to_string(),
+            second_move: GameMove::Paper {},
+            game_id,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(p2.as_ref(), &[]),
+            respond(None),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::GameIdRequired {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(p2.as_ref(), &[]),
+            respond(Some(2)),
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Reveal {
+            opponent: p2.to_string(),
+            first_move: GameMove::Rock {},
+            salt: SALT.to_string(),
+            next_commitment: None,
+            game_id: Some(2),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(p1.as_ref(), &[]), msg).unwrap();
+        assert_eq!(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: p2.to_string(),
+                amount: coins(200, "earth"),
+            }),
+            res.messages[0].msg
+        );
+
+        // the casual game is left untouched
+        let game = load_game(&deps.storage, &p1, &p2, None).unwrap();
+        assert_eq!(1, game.id);
+        assert_eq!(None, game.tournament);
+        assert_eq!(None, game.opp_move);
+    }
+
+    fn set_bet_limit(deps: DepsMut, denom: &str, min_bet: u128, max_bet: u128) {
+        let owner = MockApi::default().addr_make("owner");
+        let msg = ExecuteMsg::SetBetLimit {
+            denom: denom.to_string(),
+            min_bet: Uint128::new(min_bet),
+            max_bet: Uint128::new(max_bet),
+        };
+        execute(deps, mock_env(), mock_info(owner.as_ref(), &[]), msg).unwrap();
+    }
+
+    fn bet(deps: DepsMut, player: &Addr, funds: &[Coin]) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::BetToken {
+            first_move: GameMove::Rock {},
+            entropy: "entropy".to_string(),
+            kind: None,
+        };
+        execute(deps, mock_env(), mock_info(player.as_ref(), funds), msg)
+    }
+
+    #[test]
+    fn bet_limits() {
+        let mut deps = mock_dependencies_with_balance(&coins(10_000, "earth"));
+        setup(deps.as_mut());
+        let player = deps.api.addr_make("player");
+
+        let err = bet(deps.as_mut(), &player, &coins(50, "earth")).unwrap_err();
+        assert!(matches!(err, ContractError::DenomNotAllowed { .. }));
+
+        // only the owner manages the whitelist
+        let msg = ExecuteMsg::SetBetLimit {
+            denom: "earth".to_string(),
+            min_bet: Uint128::new(10),
+            max_bet: Uint128::new(100),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(player.as_ref(), &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Ownership(OwnershipError::NotOwner)
+        ));
+        set_bet_limit(deps.as_mut(), "earth", 10, 100);
+
+        let err = bet(deps.as_mut(), &player, &coins(5, "earth")).unwrap_err();
+        assert!(matches!(err, ContractError::BetTooSmall { .. }));
+        let err = bet(deps.as_mut(), &player, &coins(101, "earth")).unwrap_err();
+        assert!(matches!(err, ContractError::BetTooLarge { .. }));
+        bet(deps.as_mut(), &player, &coins(100, "earth")).unwrap();
+
+        let res: BetLimitsResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::BetLimits {}).unwrap()).unwrap();
+        assert_eq!(
+            res.limits,
+            vec![BetLimitResponse {
+                denom: "earth".to_string(),
+                min_bet: Uint128::new(10),
+                max_bet: Uint128::new(100),
+            }]
+        );
+
+        let owner = MockApi::default().addr_make("owner");
+        let msg = ExecuteMsg::RemoveBetLimit {
+            denom: "earth".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(owner.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+        let err = bet(deps.as_mut(), &player, &coins(50, "earth")).unwrap_err();
+        assert!(matches!(err, ContractError::DenomNotAllowed { .. }));
+    }
+
+    #[test]
+    fn bet_requires_house_reserve() {
+        // the balance already contains the incoming bet
+        let mut deps = mock_dependencies_with_balance(&coins(150, "earth"));
+        setup(deps.as_mut());
+        set_bet_limit(deps.as_mut(), "earth", 1, 1_000);
+        let player = deps.api.addr_make("player");
+
+        let err = bet(deps.as_mut(), &player, &coins(100, "earth")).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientReserve {}));
+        bet(deps.as_mut(), &player, &coins(75, "earth")).unwrap();
+
+        // escrowed PvP stakes do not back house bets
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Rock {},
+            &coins(100, "earth"),
+            None,
+            None,
+        )
+        .unwrap();
+        let err = bet(deps.as_mut(), &player, &coins(75, "earth")).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientReserve {}));
+        bet(deps.as_mut(), &player, &coins(25, "earth")).unwrap();
+    }
+
+    #[test]
+    fn bet_pays_twice_the_bet() {
+        let mut deps = mock_dependencies_with_balance(&coins(u128::MAX, "earth"));
+        setup(deps.as_mut());
+        set_bet_limit(deps.as_mut(), "earth", 1, u128::MAX);
+        let player = deps.api.addr_make("player");
+
+        // the payout overflows instead of being truncated
+        let err = bet(deps.as_mut(), &player, &coins(u128::MAX, "earth")).unwrap_err();
+        assert!(matches!(err, ContractError::Overflow(_)));
+
+        for _ in 0..10 {
+            let res = bet(deps.as_mut(), &player, &coins(1 << 70, "earth")).unwrap();
+            let result = &res.attributes[1].value;
+            let paid = match res.messages.first().map(|m| &m.msg) {
+                Some(CosmosMsg::Bank(BankMsg::Send { amount, .. })) => amount[0].amount,
+                _ => Uint128::zero(),
+            };
+            let expected = match result {
+                r if *r == GameResult::PlayerWins().to_string() => Uint128::new(1 << 71),
+                r if *r == GameResult::Tie {}.to_string() => Uint128::new(1 << 70),
+                _ => Uint128::zero(),
+            };
+            assert_eq!(expected, paid);
+        }
+    }
+
+    #[test]
+    fn structured_errors() {
+        let mut deps = mock_dependencies_with_balance(&coins(1_000, "earth"));
+        setup(deps.as_mut());
+        set_bet_limit(deps.as_mut(), "earth", 1, 100);
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+
+        // wrong funds
+        let err = bet(deps.as_mut(), &host, &[]).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Payment(PaymentError::NoFunds {})
+        ));
+        let err = bet(deps.as_mut(), &host, &coins(0, "earth")).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Payment(PaymentError::NoFunds {})
+        ));
+        let funds = [coin(10, "earth"), coin(10, "moon")];
+        let err = bet(deps.as_mut(), &host, &funds).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Payment(PaymentError::MultipleDenoms {})
+        ));
+
+        // insufficient house balance, for a player win and for a withdrawal
+        set_bet_limit(deps.as_mut(), "earth", 1, 1_000);
+        let err = bet(deps.as_mut(), &host, &coins(600, "earth")).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientReserve {}));
+        let owner = deps.api.addr_make("owner");
+        let withdraw = |amount| ExecuteMsg::Withdraw {
+            coin: coin(amount, "earth"),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(owner.as_ref(), &[]),
+            withdraw(1_001),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientReserve {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(owner.as_ref(), &[]),
+            withdraw(1_000),
+        )
+        .unwrap();
+
+        // self play, directly and through the lobby
+        let err = new_game(
+            deps.as_mut(),
+            &host,
+            Some(&host),
+            GameMove::Rock {},
+            &[],
+            None,
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SelfPlay {}));
+        new_game(
+            deps.as_mut(),
+            &host,
+            None,
+            GameMove::Rock {},
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+        let msg = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: GameMove::Paper {},
+            game_id: Some(1),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(host.as_ref(), &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SelfPlay {}));
+
+        // missing and unfinished games
+        let msg = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: GameMove::Paper {},
+            game_id: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::GameNotFound {}));
+        let result = QueryMsg::GetResult {
+            host: host.to_string(),
+            opponent: opponent.to_string(),
+        };
+        let err = query(deps.as_ref(), mock_env(), result.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::GameNotFound {}));
+
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Rock {},
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+        let err = query(deps.as_ref(), mock_env(), result).unwrap_err();
+        assert!(matches!(err, ContractError::GameNotFinished {}));
+
+        // a game is answered once
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyResponded {}));
+    }
+
+    #[test]
+    fn house_edge_and_stats() {
+        let mut deps = mock_dependencies_with_balance(&coins(10_000, "earth"));
+        setup(deps.as_mut());
+        set_bet_limit(deps.as_mut(), "earth", 1, 1_000);
+        let owner = deps.api.addr_make("owner");
+        let player = deps.api.addr_make("player");
+
+        let msg = ExecuteMsg::SetHouseEdge {
+            house_edge_bps: 500,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(player.as_ref(), &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Ownership(OwnershipError::NotOwner)
+        ));
+        let too_high = ExecuteMsg::SetHouseEdge {
+            house_edge_bps: 10_001,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(owner.as_ref(), &[]),
+            too_high,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidHouseEdge {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(owner.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+
+        // a win pays the bet back plus the winnings minus 5%
+        let mut paid_out = Uint128::zero();
+        for _ in 0..10 {
+            let res = bet(deps.as_mut(), &player, &coins(100, "earth")).unwrap();
+            let result = &res.attributes[1].value;
+            let expected = match result {
+                r if *r == GameResult::PlayerWins().to_string() => Uint128::new(195),
+                r if *r == GameResult::Tie {}.to_string() => Uint128::new(100),
+                _ => Uint128::zero(),
+            };
+            let paid = match res.messages.first().map(|m| &m.msg) {
+                Some(CosmosMsg::Bank(BankMsg::Send { amount, .. })) => amount[0].amount,
+                _ => Uint128::zero(),
+            };
+            assert_eq!(expected, paid);
+            paid_out += paid;
+        }
+
+        let res: HouseStatsResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::HouseStats {}).unwrap()).unwrap();
+        assert_eq!(500, res.house_edge_bps);
+        assert_eq!(
+            res.stats,
+            vec![DenomStatsResponse {
+                denom: "earth".to_string(),
+                wagered: Uint128::new(1_000),
+                paid_out,
+                net_profit: Int128::new(1_000 - paid_out.u128() as i128),
+                reserved: Uint128::zero(),
+            }]
+        );
+
+        // escrowed stakes cannot be withdrawn
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Rock {},
+            &coins(100, "earth"),
+            None,
+            None,
+        )
+        .unwrap();
+        let withdraw = |amount| ExecuteMsg::Withdraw {
+            coin: coin(amount, "earth"),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(owner.as_ref(), &[]),
+            withdraw(10_000),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientReserve {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(owner.as_ref(), &[]),
+            withdraw(9_900),
+        )
+        .unwrap();
     }
 
     #[test]
-    fn start_game_with_same_host_diff_opponent() {
-        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
-        let info = mock_info("creator", &coins(1000, "earth"));
-        let msg = ExecuteMsg::StartGame {
-            opponent: "opponent".to_string(),
-            first_move: GameMove::Paper {},
+    fn ownership_pause_and_migrate() {
+        let mut deps = mock_dependencies_with_balance(&coins(10_000, "earth"));
+        let creator = deps.api.addr_make("creator");
+        let owner = deps.api.addr_make("owner");
+        let new_owner = deps.api.addr_make("new_owner");
+        let player = deps.api.addr_make("player");
+
+        // the configured owner administers jaken, not the instantiating account
+        let msg = InstantiateMsg {
+            owner: owner.clone(),
+            prng_seed: "seed".to_string(),
+            game_timeout: None,
+            beacon_pubkey: None,
+            beacon_schedule: None,
+            verifying_keys: None,
+            house_edge_bps: None,
+            badge_collection: None,
+        };
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(creator.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+        set_bet_limit(deps.as_mut(), "earth", 1, 1_000);
+        let admin = |deps: DepsMut, sender: &Addr, msg: ExecuteMsg| {
+            execute(deps, mock_env(), mock_info(sender.as_ref(), &[]), msg)
         };
 
-        // try to start game
-        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let err = admin(
+            deps.as_mut(),
+            &creator,
+            ExecuteMsg::SetPaused { paused: true },
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Ownership(OwnershipError::NotOwner)
+        ));
 
-        let msg = ExecuteMsg::StartGame {
-            opponent: "opponent2".to_string(),
-            first_move: GameMove::Paper {},
+        // ownership only moves once the new owner accepts
+        let transfer = cw_ownable::Action::TransferOwnership {
+            new_owner: new_owner.to_string(),
+            expiry: None,
         };
+        admin(deps.as_mut(), &owner, ExecuteMsg::UpdateOwnership(transfer)).unwrap();
+        let ownership: cw_ownable::Ownership<Addr> =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap()).unwrap();
+        assert_eq!(Some(owner.clone()), ownership.owner);
+        assert_eq!(Some(new_owner.clone()), ownership.pending_owner);
+        let accept = ExecuteMsg::UpdateOwnership(cw_ownable::Action::AcceptOwnership);
+        let err = admin(deps.as_mut(), &player, accept.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Ownership(OwnershipError::NotPendingOwner)
+        ));
+        admin(deps.as_mut(), &new_owner, accept).unwrap();
 
-        // try to start second game
-        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        // a challenge and a tournament from before the pause
+        new_game(
+            deps.as_mut(),
+            &creator,
+            None,
+            GameMove::Rock {},
+            &[],
+            None,
+            None,
+        )
+        .unwrap();
+        let msg = ExecuteMsg::CreateTournament {
+            entry_fee: coin(100, "earth"),
+            max_players: 4,
+            organizer_cut_bps: None,
+            prize_split: None,
+            timeout: None,
+        };
+        admin(deps.as_mut(), &creator, msg).unwrap();
+        let rival = deps.api.addr_make("rival");
+        for entrant in [&player, &rival] {
+            register(deps.as_mut(), entrant, &coins(100, "earth")).unwrap();
+        }
+        let msg = ExecuteMsg::StartTournament { tournament_id: 1 };
+        admin(deps.as_mut(), &creator, msg).unwrap();
 
-        // load games map
-        let game1 = GAME
-            .load(
-                &deps.storage,
-                (info.sender.clone(), Addr::unchecked("opponent")),
+        let pause = ExecuteMsg::SetPaused { paused: true };
+        let err = admin(deps.as_mut(), &owner, pause.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Ownership(OwnershipError::NotOwner)
+        ));
+        admin(deps.as_mut(), &new_owner, pause).unwrap();
+        let res: PausedResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::Paused {}).unwrap()).unwrap();
+        assert!(res.paused);
+
+        // no new bets or games while paused
+        let err = bet(deps.as_mut(), &player, &coins(100, "earth")).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+        let err = new_game(
+            deps.as_mut(),
+            &player,
+            None,
+            GameMove::Rock {},
+            &[],
+            None,
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+        let msg = ExecuteMsg::Respond {
+            host: creator.to_string(),
+            second_move: GameMove::Paper {},
+            game_id: Some(1),
+        };
+        let err = admin(deps.as_mut(), &player, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+        // the first player of the pairing hosts the match
+        let host = round_matches(&deps.storage, 1, 1).unwrap()[0]
+            .player1
+            .clone();
+        let msg = ExecuteMsg::StartMatch {
+            tournament_id: 1,
+            commitment: commitment(&GameMove::Rock {}),
+        };
+        let err = admin(deps.as_mut(), &host, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+        admin(
+            deps.as_mut(),
+            &new_owner,
+            ExecuteMsg::SetPaused { paused: false },
+        )
+        .unwrap();
+        bet(deps.as_mut(), &player, &coins(100, "earth")).unwrap();
+
+        let before = RANDOM.load(&deps.storage).unwrap();
+        let reseed = ExecuteMsg::Reseed {
+            prng_seed: "fresh seed".to_string(),
+        };
+        let err = admin(deps.as_mut(), &player, reseed.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Ownership(OwnershipError::NotOwner)
+        ));
+        admin(deps.as_mut(), &new_owner, reseed).unwrap();
+        assert_ne!(before, RANDOM.load(&deps.storage).unwrap());
+
+        // migrations only upgrade jaken itself
+        migrate(deps.as_mut(), mock_env(), Empty {}).unwrap();
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+        migrate(deps.as_mut(), mock_env(), Empty {}).unwrap_err();
+        set_contract_version(&mut deps.storage, "crates.io:other", "0.1.0").unwrap();
+        migrate(deps.as_mut(), mock_env(), Empty {}).unwrap_err();
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.1.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), Empty {}).unwrap();
+        assert_eq!("0.1.0", res.attributes[1].value);
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(CONTRACT_VERSION, version.version);
+    }
+
+    #[test]
+    fn migrate_legacy_state() {
+        let mut deps = mock_dependencies_with_balance(&coins(10_000, "earth"));
+        let owner = deps.api.addr_make("owner");
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+
+        // storage of the first jaken release, with a game in its layout
+        // the first release shipped with the current version number
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, CONTRACT_VERSION).unwrap();
+        let legacy = format!(r#"{{"owner":"{}"}}"#, owner);
+        deps.storage.set(b"state", legacy.as_bytes());
+        RANDOM
+            .save(&mut deps.storage, &seed_random("seed"))
+            .unwrap();
+        let legacy_game = br#"{"host":"HOST","opponent":"OPPONENT","host_move":{"rock":{}},"opp_move":null,"result":null}"#;
+        let legacy_game = String::from_utf8_lossy(legacy_game)
+            .replace("HOST", host.as_str())
+            .replace("OPPONENT", opponent.as_str());
+        let key = LEGACY_GAMES.key((host.clone(), opponent.clone()));
+        deps.storage.set(&key, legacy_game.as_bytes());
+
+        // the game held no stake, it is dropped without a refund
+        let res = migrate(deps.as_mut(), mock_env(), Empty {}).unwrap();
+        assert_eq!("1", res.attributes[3].value);
+        assert!(res.messages.is_empty());
+        assert!(LEGACY_GAMES.is_empty(&deps.storage));
+        let ownership: cw_ownable::Ownership<Addr> =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap()).unwrap();
+        assert_eq!(Some(owner.clone()), ownership.owner);
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(DEFAULT_GAME_TIMEOUT, state.game_timeout);
+        assert!(!state.paused);
+
+        // the owner administers the migrated contract and the players start over
+        set_bet_limit(deps.as_mut(), "earth", 1, 1_000);
+        bet(deps.as_mut(), &host, &coins(100, "earth")).unwrap();
+        new_game(
+            deps.as_mut(),
+            &host,
+            Some(&opponent),
+            GameMove::Rock {},
+            &coins(100, "earth"),
+            None,
+            None,
+        )
+        .unwrap();
+
+        // a second migration leaves the new layout alone
+        let res = migrate(deps.as_mut(), mock_env(), Empty {}).unwrap();
+        assert_eq!(3, res.attributes.len());
+
+        // storage in neither layout is not mistaken for the current one
+        let mut deps = mock_dependencies_with_balance(&coins(10_000, "earth"));
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, CONTRACT_VERSION).unwrap();
+        deps.storage.set(b"state", b"{}");
+        migrate(deps.as_mut(), mock_env(), Empty {}).unwrap_err();
+    }
+
+    #[test]
+    fn rules_engine() {
+        let moves = GameKind::Rpsls.moves();
+        for game_move in moves {
+            assert_eq!(
+                Ordering::Equal,
+                GameKind::Rpsls.resolve(game_move, game_move)
+            );
+            // every move beats two others and loses to the remaining two
+            let wins = moves
+                .iter()
+                .filter(|other| GameKind::Rpsls.beats(game_move, other))
+                .count();
+            let losses = moves
+                .iter()
+                .filter(|other| GameKind::Rpsls.beats(other, game_move))
+                .count();
+            assert_eq!((2, 2), (wins, losses));
+        }
+        // rock paper scissors keeps its classic relation inside the extended game
+        for first in GameKind::Rps.moves() {
+            for second in GameKind::Rps.moves() {
+                assert_eq!(
+                    GameKind::Rps.resolve(first, second),
+                    GameKind::Rpsls.resolve(first, second)
+                );
+            }
+        }
+        assert_eq!(
+            Ordering::Greater,
+            GameKind::Rpsls.resolve(&GameMove::Spock {}, &GameMove::Rock {})
+        );
+        assert_eq!(
+            Ordering::Less,
+            GameKind::Rpsls.resolve(&GameMove::Paper {}, &GameMove::Lizard {})
+        );
+
+        // every move is drawn by as many bytes, the bytes past them are skipped
+        for kind in [GameKind::Rps, GameKind::Rpsls] {
+            let moves = kind.moves();
+            let bound = 256 - 256 % moves.len();
+            for game_move in moves {
+                let draws = (0..bound)
+                    .filter(|byte| kind.rand_move(&[*byte as u8]) == *game_move)
+                    .count();
+                assert_eq!(bound / moves.len(), draws);
+            }
+        }
+        assert_eq!(GameMove::Lizard {}, GameKind::Rpsls.rand_move(&[255, 3]));
+        // no byte is kept, the draw rehashes them rather than fall back to a fixed move
+        assert_eq!(
+            GameKind::Rps.rand_move(&sha_256(&[255, 255])),
+            GameKind::Rps.rand_move(&[255, 255])
+        );
+    }
+
+    #[test]
+    fn rpsls_games_and_bets() {
+        let mut deps = mock_dependencies_with_balance(&coins(10_000, "earth"));
+        setup(deps.as_mut());
+        set_bet_limit(deps.as_mut(), "earth", 1, 1_000);
+        let host = deps.api.addr_make("host");
+        let opponent = deps.api.addr_make("opponent");
+        let player = deps.api.addr_make("player");
+
+        let start = |deps: DepsMut, opponent: &Addr, kind: GameKind, circuit: Option<String>| {
+            let msg = ExecuteMsg::StartGame {
+                opponent: Some(opponent.to_string()),
+                commitment: commitment(&GameMove::Spock {}),
+                timeout: None,
+                circuit,
+                rounds: None,
+                kind: Some(kind),
+            };
+            execute(deps, mock_env(), mock_info(host.as_ref(), &[]), msg)
+        };
+
+        // outcome proofs only cover the three classic moves
+        let err = start(
+            deps.as_mut(),
+            &opponent,
+            GameKind::Rpsls,
+            Some("outcome".to_string()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedGameKind {}));
+
+        // the host cannot open a move outside the rule set of the game
+        start(deps.as_mut(), &opponent, GameKind::Rps, None).unwrap();
+        let msg = ExecuteMsg::Respond {
+            host: host.to_string(),
+            second_move: GameMove::Lizard {},
+            game_id: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(opponent.as_ref(), &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMove { .. }));
+        respond(deps.as_mut(), &host, &opponent, GameMove::Rock {}, &[]);
+        let err = reveal(deps.as_mut(), &host, &opponent, GameMove::Spock {}, None).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMove { .. }));
+
+        // spock vaporizes rock
+        let rival = deps.api.addr_make("rival");
+        start(deps.as_mut(), &rival, GameKind::Rpsls, None).unwrap();
+        respond(deps.as_mut(), &host, &rival, GameMove::Rock {}, &[]);
+        reveal(deps.as_mut(), &host, &rival, GameMove::Spock {}, None).unwrap();
+        let msg = QueryMsg::GetResult {
+            host: host.to_string(),
+            opponent: rival.to_string(),
+        };
+        let res: ResultResponse =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(GameResult::HostWins {}, res.result);
+
+        // house bets resolve with the same rules
+        let lizard = |kind| ExecuteMsg::BetToken {
+            first_move: GameMove::Lizard {},
+            entropy: "entropy".to_string(),
+            kind,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(player.as_ref(), &coins(100, "earth")),
+            lizard(None),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMove { .. }));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(player.as_ref(), &coins(100, "earth")),
+            lizard(Some(GameKind::Rpsls)),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn badges_are_minted() {
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        setup(deps.as_mut());
+        let owner = deps.api.addr_make("owner");
+        let collection = deps.api.addr_make("collection");
+        let alice = deps.api.addr_make("alice");
+        let bob = deps.api.addr_make("bob");
+
+        let msg = ExecuteMsg::SetBadgeCollection {
+            collection: Some(collection.to_string()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice.as_ref(), &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Ownership(OwnershipError::NotOwner)
+        ));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(owner.as_ref(), &[]),
+            msg,
+        )
+        .unwrap();
+
+        let win = |mut deps: DepsMut| -> Vec<CosmosMsg> {
+            new_game(
+                deps.branch(),
+                &alice,
+                Some(&bob),
+                GameMove::Rock {},
+                &[],
+                None,
+                None,
+            )
+            .unwrap();
+            respond(deps.branch(), &alice, &bob, GameMove::Scissors {}, &[]);
+            let res = reveal(deps, &alice, &bob, GameMove::Rock {}, None).unwrap();
+            res.messages.into_iter().map(|m| m.msg).collect()
+        };
+        let mint = |token_id: u64, owner: &Addr, badge: Badge| -> CosmosMsg {
+            let msg = Cw721ExecuteMsg::<Extension, Empty>::Mint {
+                token_id: token_id.to_string(),
+                owner: owner.to_string(),
+                token_uri: Some(badge.token_uri().unwrap()),
+                extension: None,
+            };
+            WasmMsg::Execute {
+                contract_addr: collection.to_string(),
+                msg: to_json_binary(&msg).unwrap(),
+                funds: vec![],
+            }
+            .into()
+        };
+
+        // both players finish their first game
+        assert_eq!(
+            vec![
+                mint(1, &alice, Badge::FirstGame {}),
+                mint(2, &bob, Badge::FirstGame {}),
+            ],
+            win(deps.as_mut())
+        );
+        assert!(win(deps.as_mut()).is_empty());
+        assert_eq!(
+            vec![mint(3, &alice, Badge::WinStreak { wins: 3 })],
+            win(deps.as_mut())
+        );
+
+        let metadata = Badge::WinStreak { wins: 3 }.token_uri().unwrap();
+        let json =
+            base64::decode(metadata.trim_start_matches("data:application/json;base64,")).unwrap();
+        let metadata: Metadata = from_json(json).unwrap();
+        assert_eq!("Won 3 jaken games in a row", metadata.description);
+
+        let res: BadgeCollectionResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::BadgeCollection {}).unwrap())
+                .unwrap();
+        assert_eq!(Some(collection), res.collection);
+    }
+
+    mod tokens {
+        use super::*;
+        use cosmwasm_std::{to_json_binary, ContractResult, SystemResult, WasmMsg, WasmQuery};
+        use cw20::{BalanceResponse, Cw20ExecuteMsg};
+
+        fn allow_token(deps: DepsMut) -> Addr {
+            let owner = MockApi::default().addr_make("owner");
+            let token = MockApi::default().addr_make("token");
+            let msg = ExecuteMsg::AllowCw20 {
+                token: token.to_string(),
+            };
+            execute(deps, mock_env(), mock_info(owner.as_ref(), &[]), msg).unwrap();
+            token
+        }
+
+        fn send(
+            deps: DepsMut,
+            token: &Addr,
+            sender: &Addr,
+            amount: u128,
+            msg: ReceiveMsg,
+        ) -> Result<Response, ContractError> {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_json_binary(&msg).unwrap(),
+            });
+            execute(deps, mock_env(), mock_info(token.as_ref(), &[]), msg)
+        }
+
+        fn transfer(token: &Addr, recipient: &Addr, amount: u128) -> CosmosMsg {
+            WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: Uint128::new(amount),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into()
+        }
+
+        #[test]
+        fn staked_game_pays_out_tokens() {
+            let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+            setup(deps.as_mut());
+            let host = deps.api.addr_make("host");
+            let opponent = deps.api.addr_make("opponent");
+            let start = ReceiveMsg::StartGame {
+                opponent: Some(opponent.to_string()),
+                commitment: commitment(&GameMove::Rock {}),
+                timeout: None,
+                circuit: None,
+                rounds: None,
+                kind: None,
+            };
+
+            // only tokens the owner accepted
+            let token = deps.api.addr_make("token");
+            let err = send(deps.as_mut(), &token, &host, 100, start.clone()).unwrap_err();
+            assert!(matches!(err, ContractError::Cw20NotAccepted { .. }));
+            let token = allow_token(deps.as_mut());
+
+            send(deps.as_mut(), &token, &host, 100, start).unwrap();
+            let game = load_game(&deps.storage, &host, &opponent, None).unwrap();
+            assert_eq!(Some(coin(100, format!("cw20:{}", token))), game.stake);
+
+            // the tokens cannot be faked with a native denom
+            let msg = ExecuteMsg::Respond {
+                host: host.to_string(),
+                second_move: GameMove::Scissors {},
+                game_id: None,
+            };
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(opponent.as_ref(), &coins(100, format!("cw20:{}", token))),
+                msg,
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::DenomNotAllowed { .. }));
+
+            let respond = ReceiveMsg::Respond {
+                host: host.to_string(),
+                second_move: GameMove::Scissors {},
+                game_id: None,
+            };
+            send(deps.as_mut(), &token, &opponent, 100, respond).unwrap();
+
+            let res = reveal(deps.as_mut(), &host, &opponent, GameMove::Rock {}, None).unwrap();
+            assert_eq!(transfer(&token, &host, 200), res.messages[0].msg);
+        }
+
+        #[test]
+        fn bets_and_withdraw_in_tokens() {
+            let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+            setup(deps.as_mut());
+            let token = allow_token(deps.as_mut());
+            let denom = format!("cw20:{}", token);
+            set_bet_limit(deps.as_mut(), &denom, 1, 1_000);
+
+            // the house holds 1000 tokens
+            deps.querier.update_wasm(|query| match query {
+                WasmQuery::Smart { .. } => SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&BalanceResponse {
+                        balance: Uint128::new(1_000),
+                    })
+                    .unwrap(),
+                )),
+                _ => panic!("unexpected query"),
+            });
+
+            let player = deps.api.addr_make("player");
+            let bet = ReceiveMsg::BetToken {
+                first_move: GameMove::Rock {},
+                entropy: "entropy".to_string(),
+                kind: None,
+            };
+            let err = send(deps.as_mut(), &token, &player, 501, bet.clone()).unwrap_err();
+            assert!(matches!(err, ContractError::InsufficientReserve {}));
+
+            let res = send(deps.as_mut(), &token, &player, 100, bet).unwrap();
+            let payout = match res.attributes[1].value.as_str() {
+                "Player Wins!" => vec![transfer(&token, &player, 200)],
+                "Game is Tie !" => vec![transfer(&token, &player, 100)],
+                _ => vec![],
+            };
+            assert_eq!(
+                payout,
+                res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>()
+            );
+
+            let owner = deps.api.addr_make("owner");
+            let msg = ExecuteMsg::Withdraw {
+                coin: coin(300, &denom),
+            };
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(owner.as_ref(), &[]),
+                msg,
             )
             .unwrap();
-        let game2 = GAME
-            .load(
-                &deps.storage,
-                (info.sender.clone(), Addr::unchecked("opponent2")),
+            assert_eq!(transfer(&token, &owner, 300), res.messages[0].msg);
+        }
+
+        #[test]
+        fn tournament_entry_fee_in_tokens() {
+            let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+            setup(deps.as_mut());
+            let organizer = deps.api.addr_make("organizer");
+            let p1 = deps.api.addr_make("p1");
+            let p2 = deps.api.addr_make("p2");
+            let token = deps.api.addr_make("token");
+            let create = ExecuteMsg::CreateTournament {
+                entry_fee: coin(100, cw20_denom(&token)),
+                max_players: 2,
+                organizer_cut_bps: None,
+                prize_split: None,
+                timeout: None,
+            };
+
+            // the fee could not be paid in a token the owner did not accept
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(organizer.as_ref(), &[]),
+                create.clone(),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::Cw20NotAccepted { .. }));
+            allow_token(deps.as_mut());
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(organizer.as_ref(), &[]),
+                create,
             )
             .unwrap();
 
-        assert_eq!(info.sender, game1.host);
-        assert_eq!(GameMove::Paper {}, game1.host_move);
-        assert_eq!(Addr::unchecked("opponent"), game1.opponent);
-        assert_eq!(None, game1.opp_move);
-        assert_eq!(None, game1.result);
+            let register = ReceiveMsg::RegisterTournament { tournament_id: 1 };
+            let err = send(deps.as_mut(), &token, &p1, 50, register.clone()).unwrap_err();
+            assert!(matches!(err, ContractError::EntryFeeMismatch { .. }));
+            send(deps.as_mut(), &token, &p1, 100, register.clone()).unwrap();
+            send(deps.as_mut(), &token, &p2, 100, register).unwrap();
 
-        assert_eq!(info.sender, game2.host);
-        assert_eq!(GameMove::Paper {}, game2.host_move);
-        assert_eq!(Addr::unchecked("opponent2"), game2.opponent);
-        assert_eq!(None, game2.opp_move);
-        assert_eq!(None, game2.result);
+            let msg = ExecuteMsg::StartMatch {
+                tournament_id: 1,
+                commitment: commitment(&GameMove::Rock {}),
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(p1.as_ref(), &[]), msg).unwrap();
+            respond(deps.as_mut(), &p1, &p2, GameMove::Scissors {}, &[]);
+            let res = reveal(deps.as_mut(), &p1, &p2, GameMove::Rock {}, None).unwrap();
+            assert_eq!(transfer(&token, &p1, 200), res.messages[0].msg);
+        }
+    }
+
+    mod nfts {
+        use super::*;
+        use cosmwasm_std::{to_json_binary, WasmMsg};
+        use cw721::Cw721ReceiveMsg;
+        use cw721_base::msg::ExecuteMsg as Cw721ExecuteMsg;
+        use cw721_base::Extension;
+
+        fn allow_collection(deps: DepsMut, name: &str) -> Addr {
+            let owner = MockApi::default().addr_make("owner");
+            let collection = MockApi::default().addr_make(name);
+            let msg = ExecuteMsg::AllowCollection {
+                collection: collection.to_string(),
+            };
+            execute(deps, mock_env(), mock_info(owner.as_ref(), &[]), msg).unwrap();
+            collection
+        }
+
+        fn send_nft(
+            deps: DepsMut,
+            collection: &Addr,
+            sender: &Addr,
+            token_id: &str,
+            msg: NftReceiveMsg,
+        ) -> Result<Response, ContractError> {
+            let msg = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: sender.to_string(),
+                token_id: token_id.to_string(),
+                msg: to_json_binary(&msg).unwrap(),
+            });
+            execute(deps, mock_env(), mock_info(collection.as_ref(), &[]), msg)
+        }
+
+        fn start(opponent: &Addr, game_move: GameMove) -> NftReceiveMsg {
+            NftReceiveMsg::StartGame {
+                opponent: Some(opponent.to_string()),
+                commitment: commitment(&game_move),
+                timeout: None,
+                circuit: None,
+                rounds: None,
+                kind: None,
+            }
+        }
+
+        fn respond_with(host: &Addr, second_move: GameMove) -> NftReceiveMsg {
+            NftReceiveMsg::Respond {
+                host: host.to_string(),
+                second_move,
+                game_id: None,
+            }
+        }
+
+        fn transfer(collection: &Addr, recipient: &Addr, token_id: &str) -> CosmosMsg {
+            WasmMsg::Execute {
+                contract_addr: collection.to_string(),
+                msg: to_json_binary(&Cw721ExecuteMsg::<Extension, Empty>::TransferNft {
+                    recipient: recipient.to_string(),
+                    token_id: token_id.to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into()
+        }
+
+        #[test]
+        fn nft_challenges_are_not_unstaked() {
+            let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+            setup(deps.as_mut());
+            let host = deps.api.addr_make("host");
+            let punks = allow_collection(deps.as_mut(), "punks");
+
+            new_game(
+                deps.as_mut(),
+                &host,
+                None,
+                GameMove::Rock {},
+                &[],
+                None,
+                None,
+            )
+            .unwrap();
+            let msg = NftReceiveMsg::StartGame {
+                opponent: None,
+                commitment: commitment(&GameMove::Rock {}),
+                timeout: None,
+                circuit: None,
+                rounds: None,
+                kind: None,
+            };
+            send_nft(deps.as_mut(), &punks, &host, "1", msg).unwrap();
+
+            assert_eq!(vec![1, 2], lobby_ids(deps.as_ref(), None, None));
+            assert_eq!(vec![1], lobby_ids(deps.as_ref(), Some(""), None));
+        }
+
+        #[test]
+        fn winner_takes_both_nfts() {
+            let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+            setup(deps.as_mut());
+            let host = deps.api.addr_make("host");
+            let opponent = deps.api.addr_make("opponent");
+
+            // only collections the owner accepted
+            let punks = deps.api.addr_make("punks");
+            let err = send_nft(
+                deps.as_mut(),
+                &punks,
+                &host,
+                "1",
+                start(&opponent, GameMove::Rock {}),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::CollectionNotAccepted { .. }));
+            let punks = allow_collection(deps.as_mut(), "punks");
+            let apes = allow_collection(deps.as_mut(), "apes");
+            let res: CollectionsResponse =
+                from_json(query(deps.as_ref(), mock_env(), QueryMsg::Collections {}).unwrap())
+                    .unwrap();
+            assert_eq!(2, res.collections.len());
+
+            send_nft(
+                deps.as_mut(),
+                &punks,
+                &host,
+                "1",
+                start(&opponent, GameMove::Rock {}),
+            )
+            .unwrap();
+            let game = load_game(&deps.storage, &host, &opponent, None).unwrap();
+            assert_eq!(
+                Some(Nft {
+                    collection: punks.clone(),
+                    token_id: "1".to_string(),
+                }),
+                game.host_nft
+            );
+
+            // the opponent has to put up a token as well
+            let msg = ExecuteMsg::Respond {
+                host: host.to_string(),
+                second_move: GameMove::Scissors {},
+                game_id: None,
+            };
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(opponent.as_ref(), &[]),
+                msg,
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::NftStakeMismatch {}));
+
+            // any accepted collection matches the stake
+            send_nft(
+                deps.as_mut(),
+                &apes,
+                &opponent,
+                "7",
+                respond_with(&host, GameMove::Scissors {}),
+            )
+            .unwrap();
+
+            let res = reveal(deps.as_mut(), &host, &opponent, GameMove::Rock {}, None).unwrap();
+            assert_eq!(transfer(&punks, &host, "1"), res.messages[0].msg);
+            assert_eq!(transfer(&apes, &host, "7"), res.messages[1].msg);
+        }
+
+        #[test]
+        fn nfts_are_returned() {
+            let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+            setup(deps.as_mut());
+            let host = deps.api.addr_make("host");
+            let opponent = deps.api.addr_make("opponent");
+            let punks = allow_collection(deps.as_mut(), "punks");
+
+            // a tie hands each token back
+            send_nft(
+                deps.as_mut(),
+                &punks,
+                &host,
+                "1",
+                start(&opponent, GameMove::Paper {}),
+            )
+            .unwrap();
+            send_nft(
+                deps.as_mut(),
+                &punks,
+                &opponent,
+                "2",
+                respond_with(&host, GameMove::Paper {}),
+            )
+            .unwrap();
+            let res = reveal(deps.as_mut(), &host, &opponent, GameMove::Paper {}, None).unwrap();
+            assert_eq!(transfer(&punks, &host, "1"), res.messages[0].msg);
+            assert_eq!(transfer(&punks, &opponent, "2"), res.messages[1].msg);
+
+            // a game nobody responded to times out with the host token returned
+            send_nft(
+                deps.as_mut(),
+                &punks,
+                &host,
+                "1",
+                start(&opponent, GameMove::Rock {}),
+            )
+            .unwrap();
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_days(2);
+            let msg = ExecuteMsg::ClaimTimeout {
+                host: host.to_string(),
+                opponent: opponent.to_string(),
+                game_id: None,
+            };
+            let res = execute(deps.as_mut(), env, mock_info(host.as_ref(), &[]), msg).unwrap();
+            assert_eq!(
+                vec![transfer(&punks, &host, "1")],
+                res.messages
+                    .iter()
+                    .map(|m| m.msg.clone())
+                    .collect::<Vec<_>>()
+            );
+
+            // the host can also cancel before a response
+            send_nft(
+                deps.as_mut(),
+                &punks,
+                &host,
+                "1",
+                start(&opponent, GameMove::Rock {}),
+            )
+            .unwrap();
+            let msg = ExecuteMsg::CancelGame {
+                opponent: opponent.to_string(),
+                game_id: None,
+            };
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(host.as_ref(), &[]),
+                msg,
+            )
+            .unwrap();
+            assert_eq!(transfer(&punks, &host, "1"), res.messages[0].msg);
+
+            // a host who never reveals forfeits the game but not the token
+            send_nft(
+                deps.as_mut(),
+                &punks,
+                &host,
+                "1",
+                start(&opponent, GameMove::Rock {}),
+            )
+            .unwrap();
+            send_nft(
+                deps.as_mut(),
+                &punks,
+                &opponent,
+                "2",
+                respond_with(&host, GameMove::Paper {}),
+            )
+            .unwrap();
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_days(2);
+            let msg = ExecuteMsg::ClaimTimeout {
+                host: host.to_string(),
+                opponent: opponent.to_string(),
+                game_id: None,
+            };
+            let res = execute(deps.as_mut(), env, mock_info(opponent.as_ref(), &[]), msg).unwrap();
+            assert_eq!("Opponent Wins!", res.attributes[5].value);
+            assert_eq!(
+                vec![
+                    transfer(&punks, &host, "1"),
+                    transfer(&punks, &opponent, "2")
+                ],
+                res.messages
+                    .iter()
+                    .take(2)
+                    .map(|m| m.msg.clone())
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    mod beacon {
+        use super::*;
+        use crate::beacon::{round_message, DST};
+        use bls12_381::{G1Affine, G2Affine, Scalar};
+        use cosmwasm_std::{Api, HashFunction};
+
+        /// local stand-in for a drand network, signing with the secret `key`
+        fn pubkey_of(key: u64) -> Binary {
+            let pubkey = G2Affine::from(G2Affine::generator() * Scalar::from(key));
+            Binary::from(pubkey.to_compressed().to_vec())
+        }
+
+        fn sign_with(key: u64, round: u64) -> Binary {
+            let point = MockApi::default()
+                .bls12_381_hash_to_g1(HashFunction::Sha256, &round_message(round), DST)
+                .unwrap();
+            let point = G1Affine::from_compressed(&point).unwrap();
+            let signature = G1Affine::from(point * Scalar::from(key));
+            Binary::from(signature.to_compressed().to_vec())
+        }
+
+        const BEACON_KEY: u64 = 7;
+
+        fn beacon_pubkey() -> Binary {
+            pubkey_of(BEACON_KEY)
+        }
+
+        fn sign_round(round: u64) -> Binary {
+            sign_with(BEACON_KEY, round)
+        }
+
+        fn setup_beacon(deps: DepsMut) {
+            let owner = MockApi::default().addr_make("owner");
+            let msg = InstantiateMsg {
+                owner: owner.clone(),
+                prng_seed: "seed".to_string(),
+                game_timeout: None,
+                beacon_pubkey: Some(beacon_pubkey()),
+                beacon_schedule: None,
+                verifying_keys: None,
+                house_edge_bps: None,
+                badge_collection: None,
+            };
+            instantiate(deps, mock_env(), mock_info(owner.as_ref(), &[]), msg).unwrap();
+        }
+
+        /// block at the time `round` is published on the default schedule
+        fn at(round: u64) -> Env {
+            let mut env = mock_env();
+            env.block.time = round_time(&default_schedule(env.block.time), round);
+            env
+        }
+
+        /// submits `round` once it is due
+        fn submit(deps: DepsMut, round: u64, signature: Binary) -> Result<Response, ContractError> {
+            submit_at(deps, at(round), round, signature)
+        }
+
+        fn submit_at(
+            deps: DepsMut,
+            env: Env,
+            round: u64,
+            signature: Binary,
+        ) -> Result<Response, ContractError> {
+            let relayer = MockApi::default().addr_make("relayer");
+            let msg = ExecuteMsg::SubmitBeacon { round, signature };
+            execute(deps, env, mock_info(relayer.as_ref(), &[]), msg)
+        }
+
+        fn bet_at(deps: DepsMut, env: Env, player: &Addr) -> Result<Response, ContractError> {
+            let msg = ExecuteMsg::BetToken {
+                first_move: GameMove::Rock {},
+                entropy: "entropy".to_string(),
+                kind: None,
+            };
+            execute(
+                deps,
+                env,
+                mock_info(player.as_ref(), &coins(100, "earth")),
+                msg,
+            )
+        }
+
+        fn settle(deps: DepsMut, bet_id: u64) -> Result<Response, ContractError> {
+            let relayer = MockApi::default().addr_make("relayer");
+            let msg = ExecuteMsg::SettleBet { bet_id };
+            execute(deps, mock_env(), mock_info(relayer.as_ref(), &[]), msg)
+        }
+
+        #[test]
+        fn bets_wait_for_the_next_round() {
+            let mut deps = mock_dependencies_with_balance(&coins(1_000, "earth"));
+            setup_beacon(deps.as_mut());
+            set_bet_limit(deps.as_mut(), "earth", 1, 1_000);
+            let player = deps.api.addr_make("player");
+
+            let res = bet(deps.as_mut(), &player, &coins(100, "earth")).unwrap();
+            assert!(res.messages.is_empty());
+            let pending: PendingBet = from_json(
+                query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::PendingBet { bet_id: 1 },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(1, pending.round);
+            assert_eq!(coin(100, "earth"), pending.bet);
+
+            // the worst case payout is reserved while the bet is pending
+            let err = bet_on(deps.as_mut(), &player, 401);
+            assert!(matches!(err, ContractError::InsufficientReserve {}));
+
+            let err = settle(deps.as_mut(), 1).unwrap_err();
+            assert!(matches!(
+                err,
+                ContractError::BeaconRoundMissing { round: 1 }
+            ));
+
+            // rounds have to be signed by the beacon and submitted in order
+            let err = submit(deps.as_mut(), 2, sign_round(2)).unwrap_err();
+            assert!(matches!(
+                err,
+                ContractError::UnexpectedBeaconRound { expected: 1 }
+            ));
+            let err = submit(deps.as_mut(), 1, sign_round(2)).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidBeaconSignature {}));
+            let err = submit_at(deps.as_mut(), mock_env(), 1, sign_round(1)).unwrap_err();
+            assert!(matches!(err, ContractError::BeaconRoundNotDue { round: 1 }));
+            submit(deps.as_mut(), 1, sign_round(1)).unwrap();
+
+            // new bets are settled against the following round
+            bet(deps.as_mut(), &player, &coins(100, "earth")).unwrap();
+            let pending: PendingBet = from_json(
+                query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::PendingBet { bet_id: 2 },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(2, pending.round);
+
+            let res = settle(deps.as_mut(), 1).unwrap();
+            let randomness = BEACON_ROUNDS.load(&deps.storage, 1).unwrap();
+            let contract_move = GameKind::Rps.rand_move(&bet_randomness(&randomness, 1));
+            let result = get_bet_result(GameKind::Rps, &GameMove::Rock {}, &contract_move);
+            assert_eq!(result.to_string(), res.attributes[3].value);
+            let payout = bet_payout(Uint128::new(100), &result, 0).unwrap();
+            let expected: Vec<CosmosMsg> = match payout.is_zero() {
+                true => vec![],
+                false => vec![send_coin(&player, coin(payout.u128(), "earth")).unwrap()],
+            };
+            assert_eq!(
+                expected,
+                res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>()
+            );
+
+            // a bet is settled only once
+            let err = settle(deps.as_mut(), 1).unwrap_err();
+            assert!(matches!(err, ContractError::BetNotFound {}));
+
+            // the beacon cannot be switched off or rotated while bets are pending
+            let owner = deps.api.addr_make("owner");
+            let msg = ExecuteMsg::SetBeacon {
+                pubkey: None,
+                schedule: None,
+            };
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(owner.as_ref(), &[]),
+                msg.clone(),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::BetsPending {}));
+            let rotate = ExecuteMsg::SetBeacon {
+                pubkey: Some(pubkey_of(9)),
+                schedule: None,
+            };
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(owner.as_ref(), &[]),
+                rotate,
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::BetsPending {}));
+
+            submit(deps.as_mut(), 2, sign_round(2)).unwrap();
+            settle(deps.as_mut(), 2).unwrap();
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(owner.as_ref(), &[]),
+                msg,
+            )
+            .unwrap();
+
+            let res: BeaconResponse =
+                from_json(query(deps.as_ref(), mock_env(), QueryMsg::Beacon {}).unwrap()).unwrap();
+            assert_eq!(
+                res,
+                BeaconResponse {
+                    pubkey: None,
+                    latest_round: 2,
+                    schedule: Some(default_schedule(mock_env().block.time)),
+                }
+            );
+        }
+
+        #[test]
+        fn a_round_has_a_single_signature() {
+            let mut deps = mock_dependencies_with_balance(&coins(1_000, "earth"));
+            setup_beacon(deps.as_mut());
+
+            // BLS signing is deterministic, the beacon cannot sign round 1 any other way
+            let signature = sign_round(1);
+            assert_eq!(signature, sign_round(1));
+            // the negated point, the other signature an ECDSA nonce could have produced,
+            // and signatures of other keys or rounds are rejected
+            let mut negated = signature.to_vec();
+            negated[0] ^= 0x20;
+            for forged in [
+                Binary::from(negated),
+                sign_with(9, 1),
+                sign_round(2),
+                Binary::from(vec![0u8; 48]),
+            ] {
+                let err = submit(deps.as_mut(), 1, forged).unwrap_err();
+                assert!(matches!(err, ContractError::InvalidBeaconSignature {}));
+            }
+
+            // the randomness follows from the round alone, as drand derives it
+            let res = submit(deps.as_mut(), 1, signature.clone()).unwrap();
+            let randomness = Binary::from(sha_256(&signature));
+            assert_eq!(randomness.to_base64(), res.attributes[2].value);
+            assert_eq!(randomness, BEACON_ROUNDS.load(&deps.storage, 1).unwrap());
+
+            // the key has to be a G2 point
+            let owner = deps.api.addr_make("owner");
+            for pubkey in [Binary::from(vec![1u8; 96]), sign_round(1)] {
+                let msg = ExecuteMsg::SetBeacon {
+                    pubkey: Some(pubkey),
+                    schedule: None,
+                };
+                let err = execute(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info(owner.as_ref(), &[]),
+                    msg,
+                )
+                .unwrap_err();
+                assert!(matches!(err, ContractError::InvalidBeaconKey {}));
+            }
+        }
+
+        #[test]
+        fn rounds_follow_the_beacon_schedule() {
+            let mut deps = mock_dependencies_with_balance(&coins(1_000, "earth"));
+            setup(deps.as_mut());
+            set_bet_limit(deps.as_mut(), "earth", 1, 1_000);
+            let owner = deps.api.addr_make("owner");
+            let player = deps.api.addr_make("player");
+
+            // a network that started long ago and publishes a round every 3 seconds
+            let schedule = BeaconSchedule {
+                genesis: mock_env().block.time.minus_seconds(3_000),
+                period: 3,
+            };
+            let msg = |period| ExecuteMsg::SetBeacon {
+                pubkey: Some(beacon_pubkey()),
+                schedule: Some(BeaconSchedule {
+                    period,
+                    ..schedule.clone()
+                }),
+            };
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(owner.as_ref(), &[]),
+                msg(0),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidBeaconPeriod {}));
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(owner.as_ref(), &[]),
+                msg(3),
+            )
+            .unwrap();
+
+            // round 1_001 is out now, round 1_002 in 3 seconds
+            assert_eq!(mock_env().block.time, round_time(&schedule, 1_001));
+            let err = submit_at(deps.as_mut(), mock_env(), 1_002, sign_round(1_002)).unwrap_err();
+            assert!(matches!(
+                err,
+                ContractError::BeaconRoundNotDue { round: 1_002 }
+            ));
+            submit_at(deps.as_mut(), mock_env(), 1_001, sign_round(1_001)).unwrap();
+            let res = bet_at(deps.as_mut(), mock_env(), &player).unwrap();
+            assert_eq!("1002", res.attributes[2].value);
+
+            // rotating the key keeps the schedule
+            submit_at(
+                deps.as_mut(),
+                at_time(&schedule, 1_002),
+                1_002,
+                sign_round(1_002),
+            )
+            .unwrap();
+            settle(deps.as_mut(), 1).unwrap();
+            let msg = ExecuteMsg::SetBeacon {
+                pubkey: Some(pubkey_of(9)),
+                schedule: None,
+            };
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(owner.as_ref(), &[]),
+                msg,
+            )
+            .unwrap();
+            let res: BeaconResponse =
+                from_json(query(deps.as_ref(), mock_env(), QueryMsg::Beacon {}).unwrap()).unwrap();
+            assert_eq!(Some(schedule), res.schedule);
+        }
+
+        fn at_time(schedule: &BeaconSchedule, round: u64) -> Env {
+            let mut env = mock_env();
+            env.block.time = round_time(schedule, round);
+            env
+        }
+
+        #[test]
+        fn bets_never_wait_on_a_published_round() {
+            let mut deps = mock_dependencies_with_balance(&coins(1_000, "earth"));
+            setup_beacon(deps.as_mut());
+            set_bet_limit(deps.as_mut(), "earth", 1, 1_000);
+            let player = deps.api.addr_make("player");
+
+            // round 1 is out but the relayer has not submitted it yet,
+            // anyone who read its signature would know the house move
+            let err = bet_at(deps.as_mut(), at(1), &player).unwrap_err();
+            assert!(matches!(
+                err,
+                ContractError::BeaconRoundPublished { round: 1 }
+            ));
+
+            // without pending bets the relayer catches up with the latest round
+            submit(deps.as_mut(), 5, sign_round(5)).unwrap();
+            let err = submit(deps.as_mut(), 4, sign_round(4)).unwrap_err();
+            assert!(matches!(
+                err,
+                ContractError::UnexpectedBeaconRound { expected: 6 }
+            ));
+            bet_at(deps.as_mut(), at(5), &player).unwrap();
+
+            // the round the bet waits on cannot be skipped
+            let err = submit(deps.as_mut(), 7, sign_round(7)).unwrap_err();
+            assert!(matches!(
+                err,
+                ContractError::UnexpectedBeaconRound { expected: 6 }
+            ));
+            submit(deps.as_mut(), 6, sign_round(6)).unwrap();
+            let res = settle(deps.as_mut(), 1).unwrap();
+            assert_eq!("1", res.attributes[1].value);
+        }
+
+        fn bet_on(deps: DepsMut, player: &Addr, amount: u128) -> ContractError {
+            bet(deps, player, &coins(amount, "earth")).unwrap_err()
+        }
+    }
+
+    mod zk {
+        use super::*;
+        use crate::msg::CircuitKey;
+        use serde::Deserialize;
+
+        const CIRCUIT: &str = "rps_outcome";
+
+        #[derive(Deserialize)]
+        struct ZkCase {
+            host_move: GameMove,
+            opp_move: GameMove,
+            result: GameResult,
+            commitment: Binary,
+            proof: Binary,
+        }
+
+        /// generated by `cargo run --example zk_fixtures`
+        #[derive(Deserialize)]
+        struct ZkFixtures {
+            verifying_key: Binary,
+            cases: Vec<ZkCase>,
+        }
+
+        fn fixtures() -> ZkFixtures {
+            from_json(include_str!("../testdata/zk_outcome.json")).unwrap()
+        }
+
+        fn setup_zk(deps: DepsMut) {
+            let owner = MockApi::default().addr_make("owner");
+            let msg = InstantiateMsg {
+                owner: owner.clone(),
+                prng_seed: "seed".to_string(),
+                game_timeout: None,
+                beacon_pubkey: None,
+                beacon_schedule: None,
+                verifying_keys: Some(vec![CircuitKey {
+                    circuit: CIRCUIT.to_string(),
+                    verifying_key: fixtures().verifying_key,
+                }]),
+                house_edge_bps: None,
+                badge_collection: None,
+            };
+            instantiate(deps, mock_env(), mock_info(owner.as_ref(), &[]), msg).unwrap();
+        }
+
+        fn start_zk_game(mut deps: DepsMut, host: &Addr, opponent: &Addr, case: &ZkCase) {
+            let msg = ExecuteMsg::StartGame {
+                opponent: Some(opponent.to_string()),
+                commitment: case.commitment.clone(),
+                timeout: None,
+                circuit: Some(CIRCUIT.to_string()),
+                rounds: None,
+                kind: None,
+            };
+            execute(
+                deps.branch(),
+                mock_env(),
+                mock_info(host.as_ref(), &[]),
+                msg,
+            )
+            .unwrap();
+
+            let msg = ExecuteMsg::Respond {
+                host: host.to_string(),
+                second_move: case.opp_move.clone(),
+                game_id: None,
+            };
+            execute(deps, mock_env(), mock_info(opponent.as_ref(), &[]), msg).unwrap();
+        }
+
+        fn prove(
+            deps: DepsMut,
+            host: &Addr,
+            opponent: &Addr,
+            result: GameResult,
+            proof: Binary,
+        ) -> Result<Response, ContractError> {
+            let msg = ExecuteMsg::ProveOutcome {
+                opponent: opponent.to_string(),
+                result,
+                proof,
+                next_commitment: None,
+                game_id: None,
+            };
+            execute(deps, mock_env(), mock_info(host.as_ref(), &[]), msg)
+        }
+
+        #[test]
+        fn proofs_settle_games_without_reveal() {
+            let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+            setup_zk(deps.as_mut());
+            let host = deps.api.addr_make("host");
+            let opponent = deps.api.addr_make("opponent");
+
+            let res: CircuitsResponse =
+                from_json(query(deps.as_ref(), mock_env(), QueryMsg::Circuits {}).unwrap())
+                    .unwrap();
+            assert_eq!(vec![CIRCUIT.to_string()], res.circuits);
+
+            for case in fixtures().cases {
+                start_zk_game(deps.as_mut(), &host, &opponent, &case);
+
+                // circuit games are settled by proof only
+                let msg = ExecuteMsg::Reveal {
+                    opponent: opponent.to_string(),
+                    first_move: case.host_move.clone(),
+                    salt: SALT.to_string(),
+                    next_commitment: None,
+                    game_id: None,
+                };
+                let err = execute(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info(host.as_ref(), &[]),
+                    msg,
+                )
+                .unwrap_err();
+                assert!(matches!(err, ContractError::ProofRequired {}));
+
+                // the proof does not hold for any other outcome
+                let wrong = match case.result {
+                    GameResult::Tie {} => GameResult::HostWins {},
+                    _ => GameResult::Tie {},
+                };
+                let err =
+                    prove(deps.as_mut(), &host, &opponent, wrong, case.proof.clone()).unwrap_err();
+                assert!(matches!(err, ContractError::InvalidProof {}));
+
+                let res = prove(
+                    deps.as_mut(),
+                    &host,
+                    &opponent,
+                    case.result.clone(),
+                    case.proof,
+                )
+                .unwrap();
+                assert_eq!(res.attributes[1].value, case.result.to_string());
+
+                // the archived game keeps the host move hidden
+                let res: GameResponse = from_json(
+                    query(
+                        deps.as_ref(),
+                        mock_env(),
+                        QueryMsg::GamesByPlayer {
+                            player: host.to_string(),
+                            start_after: None,
+                            limit: Some(1),
+                        },
+                    )
+                    .unwrap(),
+                )
+                .unwrap();
+                assert_eq!(Some(case.result), res.result[0].result);
+                assert_eq!(None, res.result[0].host_move);
+            }
+        }
+
+        #[test]
+        fn unknown_circuit_is_rejected() {
+            let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+            setup_zk(deps.as_mut());
+            let host = deps.api.addr_make("host");
+            let opponent = deps.api.addr_make("opponent");
+
+            let msg = ExecuteMsg::StartGame {
+                opponent: Some(opponent.to_string()),
+                commitment: fixtures().cases[0].commitment.clone(),
+                timeout: None,
+                circuit: Some("unknown".to_string()),
+                rounds: None,
+                kind: None,
+            };
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(host.as_ref(), &[]),
+                msg,
+            )
+            .unwrap_err();
+            assert!(matches!(
+                err,
+                ContractError::UnknownCircuit { circuit } if circuit == "unknown"
+            ));
+
+            // commitments of circuit games are field elements
+            let msg = ExecuteMsg::StartGame {
+                opponent: Some(opponent.to_string()),
+                commitment: Binary::from(vec![1u8; 20]),
+                timeout: None,
+                circuit: Some(CIRCUIT.to_string()),
+                rounds: None,
+                kind: None,
+            };
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(host.as_ref(), &[]),
+                msg,
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidCommitment {}));
+        }
     }
 }
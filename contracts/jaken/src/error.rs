@@ -1,11 +1,25 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Coin, OverflowError, StdError, Uint128};
+use cw_ownable::OwnershipError;
+use cw_utils::PaymentError;
 use thiserror::Error;
 
+use crate::rules::GameKind;
+use crate::state::GameMove;
+
 #[derive(Error, Debug)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error(transparent)]
+    Ownership(#[from] OwnershipError),
+
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -15,17 +29,164 @@ pub enum ContractError {
     #[error("This address already started a game")]
     AlreadyStarted {},
 
+    #[error("Host and opponent must be different players")]
+    SelfPlay {},
+
     #[error("Host has not started a game")]
     GameNotFound {},
 
+    #[error("The players have several games running, pick one with its game id")]
+    GameIdRequired {},
+
     #[error("The game still has no winner")]
     GameNotFinished {},
 
     #[error("You cant respond to this game")]
     UnauthorizedOpponent {},
 
+    #[error("Commitment must be a sha256 hash")]
+    InvalidCommitment {},
+
+    #[error("Opponent already responded to this game")]
+    AlreadyResponded {},
+
+    #[error("Opponent has not responded yet")]
+    NotResponded {},
+
+    #[error("Sent funds must match the game stake")]
+    StakeMismatch {},
+
+    #[error("Revealed move does not match the commitment")]
+    InvalidReveal {},
+
+    #[error("Game timeout must not be zero")]
+    InvalidTimeout {},
+
+    #[error("The game deadline has passed")]
+    Expired {},
+
+    #[error("The game deadline has not passed yet")]
+    GameNotExpired {},
+
+    #[error("Denom {denom} is not accepted for bets")]
+    DenomNotAllowed { denom: String },
+
+    #[error("Bet is below the minimum of {min_bet}")]
+    BetTooSmall { min_bet: Uint128 },
+
+    #[error("Bet is above the maximum of {max_bet}")]
+    BetTooLarge { max_bet: Uint128 },
+
+    #[error("Minimum bet must not exceed the maximum bet")]
+    InvalidBetLimit {},
+
+    #[error("House reserve cannot cover the payout")]
+    InsufficientReserve {},
+
+    #[error("Beacon public key must be a BLS12-381 G2 point")]
+    InvalidBeaconKey {},
+
+    #[error("Beacon period must not be zero")]
+    InvalidBeaconPeriod {},
+
+    #[error("Invalid beacon signature")]
+    InvalidBeaconSignature {},
+
+    #[error("Beacon is not configured")]
+    BeaconDisabled {},
+
+    #[error("Expected beacon round {expected}")]
+    UnexpectedBeaconRound { expected: u64 },
+
+    #[error("Beacon round {round} has not been submitted yet")]
+    BeaconRoundMissing { round: u64 },
+
+    #[error("Beacon round {round} is not due yet")]
+    BeaconRoundNotDue { round: u64 },
+
+    #[error("Beacon round {round} is already published, bets resume once it is submitted")]
+    BeaconRoundPublished { round: u64 },
+
+    #[error("Bets are still waiting for the beacon")]
+    BetsPending {},
+
+    #[error("Bet not found")]
+    BetNotFound {},
+
+    #[error("Invalid Groth16 verifying key")]
+    InvalidVerifyingKey {},
+
+    #[error("No verifying key registered for circuit {circuit}")]
+    UnknownCircuit { circuit: String },
+
+    #[error("Invalid outcome proof")]
+    InvalidProof {},
+
+    #[error("This game is settled with an outcome proof")]
+    ProofRequired {},
+
+    #[error("This game is settled by revealing the move")]
+    RevealRequired {},
+
+    #[error("{game_move} is not a move of {kind} games")]
+    InvalidMove { game_move: GameMove, kind: GameKind },
+
+    #[error("Outcome proofs only support rock paper scissors games")]
+    UnsupportedGameKind {},
+
     #[error("No funds available to withdraw")]
     NoFunds {},
+
+    #[error("Rounds must be an odd number from 1 to {max_rounds}")]
+    InvalidRounds { max_rounds: u32 },
+
+    #[error("The match continues, commit to the next move")]
+    CommitmentRequired {},
+
+    #[error("A tournament takes from 2 to {max_players} players")]
+    InvalidMaxPlayers { max_players: u32 },
+
+    #[error("Organizer cut cannot exceed the prize pool")]
+    InvalidOrganizerCut {},
+
+    #[error("Prize split needs up to 3 shares adding up to 10000 basis points")]
+    InvalidPrizeSplit {},
+
+    #[error("Tournament registration is closed")]
+    RegistrationClosed {},
+
+    #[error("Player is already registered")]
+    AlreadyRegistered {},
+
+    #[error("Entry fee is {entry_fee}")]
+    EntryFeeMismatch { entry_fee: Coin },
+
+    #[error("A tournament needs at least 2 players")]
+    NotEnoughPlayers {},
+
+    #[error("No open match for this player")]
+    MatchNotFound {},
+
+    #[error("Tournament games cannot be cancelled")]
+    TournamentGame {},
+
+    #[error("Token contract {token} is not accepted")]
+    Cw20NotAccepted { token: String },
+
+    #[error("NFT collection {collection} is not accepted")]
+    CollectionNotAccepted { collection: String },
+
+    #[error("An NFT has to be staked exactly when the host staked one")]
+    NftStakeMismatch {},
+
+    #[error("House edge cannot exceed 10000 basis points")]
+    InvalidHouseEdge {},
+
+    #[error("Jaken is paused, no new games or bets are accepted")]
+    Paused {},
+
+    #[error("Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
@@ -1,9 +1,22 @@
+use std::marker::PhantomData;
+
 use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{to_json_binary, Addr, Coin, CosmosMsg, DepsMut, StdError, StdResult, WasmMsg};
+use cosmwasm_std::{
+    to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, DepsMut, Empty, QuerierWrapper,
+    StdResult, Uint128, WasmMsg,
+};
+use cw20::{Cw20Contract, Cw20ExecuteMsg};
+use cw721_base::helpers::Cw721Contract;
+use cw721_base::msg::ExecuteMsg as Cw721ExecuteMsg;
+use cw721_base::Extension;
+use cw_utils::PaymentError;
 
-use crate::msg::ExecuteMsg;
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, QueryMsg, ResultResponse};
+use crate::state::{GameMove, Nft, RESERVED};
 
 /// CwTemplateContract is a wrapper around Addr that provides a lot of helpers
 /// for working with this.
@@ -16,46 +29,171 @@ impl CwTemplateContract {
     }
 
     pub fn call<T: Into<ExecuteMsg>>(&self, msg: T) -> StdResult<CosmosMsg> {
+        self.call_with_funds(msg, vec![])
+    }
+
+    pub fn call_with_funds<T: Into<ExecuteMsg>>(
+        &self,
+        msg: T,
+        funds: Vec<Coin>,
+    ) -> StdResult<CosmosMsg> {
         let msg = to_json_binary(&msg.into())?;
         Ok(WasmMsg::Execute {
             contract_addr: self.addr().into(),
             msg,
-            funds: vec![],
+            funds,
         }
         .into())
     }
+
+    /// Starts a single throw rock paper scissors game, `stake` is escrowed until it is settled.
+    /// Leave `opponent` empty to post an open challenge.
+    pub fn start_game(
+        &self,
+        opponent: Option<String>,
+        commitment: Binary,
+        stake: Vec<Coin>,
+    ) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::StartGame {
+            opponent,
+            commitment,
+            timeout: None,
+            circuit: None,
+            rounds: None,
+            kind: None,
+        };
+        self.call_with_funds(msg, stake)
+    }
+
+    /// Answers the game of `host`, `stake` has to match the host stake
+    pub fn respond(
+        &self,
+        host: String,
+        second_move: GameMove,
+        game_id: Option<u64>,
+        stake: Vec<Coin>,
+    ) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::Respond {
+            host,
+            second_move,
+            game_id,
+        };
+        self.call_with_funds(msg, stake)
+    }
+
+    pub fn reveal(
+        &self,
+        opponent: String,
+        first_move: GameMove,
+        salt: String,
+    ) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::Reveal {
+            opponent,
+            first_move,
+            salt,
+            next_commitment: None,
+            game_id: None,
+        })
+    }
+
+    /// Bets `bet` on a rock paper scissors throw against the house
+    pub fn bet(&self, first_move: GameMove, entropy: String, bet: Coin) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::BetToken {
+            first_move,
+            entropy,
+            kind: None,
+        };
+        self.call_with_funds(msg, vec![bet])
+    }
+
+    pub fn withdraw(&self, coin: Coin) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::Withdraw { coin })
+    }
+
+    pub fn query<T: DeserializeOwned>(
+        &self,
+        querier: &QuerierWrapper,
+        msg: QueryMsg,
+    ) -> StdResult<T> {
+        querier.query_wasm_smart(self.addr(), &msg)
+    }
+
+    /// Result of the last finished game between `host` and `opponent`
+    pub fn result(
+        &self,
+        querier: &QuerierWrapper,
+        host: String,
+        opponent: String,
+    ) -> StdResult<ResultResponse> {
+        self.query(querier, QueryMsg::GetResult { host, opponent })
+    }
 }
 
-pub fn validate_sent_funds(funds: Vec<Coin>) -> Result<Coin, StdError> {
-    if funds.len() != 1 {
-        return Err(StdError::generic_err(format!(
-            "multiple coin sent({:?})",
-            funds
-        )));
+/// Exactly one non zero coin, reported like `cw_utils::one_coin` otherwise
+pub fn validate_sent_funds(funds: Vec<Coin>) -> Result<Coin, PaymentError> {
+    match funds.as_slice() {
+        [] => Err(PaymentError::NoFunds {}),
+        [fund] if fund.amount.is_zero() => Err(PaymentError::NoFunds {}),
+        [fund] => Ok(fund.clone()),
+        _ => Err(PaymentError::MultipleDenoms {}),
     }
+}
+
+/// cw20 tokens are accounted for like native coins, under the denom `cw20:<token address>`
+pub const CW20_DENOM_PREFIX: &str = "cw20:";
+
+pub fn cw20_denom(token: &Addr) -> String {
+    format!("{}{}", CW20_DENOM_PREFIX, token)
+}
+
+/// Token contract of a cw20 denom, `None` for native denoms
+pub fn cw20_address(denom: &str) -> Option<Addr> {
+    denom.strip_prefix(CW20_DENOM_PREFIX).map(Addr::unchecked)
+}
 
-    let fund = &funds[0];
-    if fund.amount.is_zero() {
-        return Err(StdError::generic_err(format!(
-            "sent fund is zero amount({:?})",
-            fund
-        )));
+/// Balance of a native or cw20 denom
+pub fn query_balance(querier: &QuerierWrapper, address: &Addr, denom: &str) -> StdResult<Uint128> {
+    match cw20_address(denom) {
+        Some(token) => Cw20Contract(token).balance(querier, address),
+        None => Ok(querier.query_balance(address, denom)?.amount),
     }
-    Ok(fund.clone())
 }
 
-pub fn validate_balance(
+/// Sends native coins with a bank message and cw20 tokens with a `Transfer`
+pub fn send_coin(to: &Addr, coin: Coin) -> StdResult<CosmosMsg> {
+    match cw20_address(&coin.denom) {
+        Some(token) => Cw20Contract(token).call(Cw20ExecuteMsg::Transfer {
+            recipient: to.to_string(),
+            amount: coin.amount,
+        }),
+        None => Ok(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: vec![coin],
+        }
+        .into()),
+    }
+}
+
+/// Hands an escrowed NFT over with a cw721 `TransferNft`
+pub fn transfer_nft(to: &Addr, nft: Nft) -> StdResult<CosmosMsg> {
+    let transfer = Cw721ExecuteMsg::<Extension, Empty>::TransferNft {
+        recipient: to.to_string(),
+        token_id: nft.token_id,
+    };
+    Cw721Contract::<Empty, Empty>(nft.collection, PhantomData, PhantomData).call(transfer)
+}
+
+/// Checks that the contract balance, minus the funds reserved for players, covers `payout`
+pub fn validate_house_reserve(
     deps: &DepsMut,
-    address: &Addr,
+    contract_addr: &Addr,
     denom: &str,
-    amount: u128,
-) -> Result<bool, StdError> {
-    let balance = deps.querier.query_balance(address, denom)?;
-    if balance.amount.u128() < amount as u128 {
-        return Err(StdError::generic_err(format!(
-            "insufficient balance in address({})",
-            address
-        )));
-    }
-    Ok(true)
+    payout: Uint128,
+) -> Result<(), ContractError> {
+    let balance = query_balance(&deps.querier, contract_addr, denom)?;
+    let reserved = RESERVED.may_load(deps.storage, denom)?.unwrap_or_default();
+    if balance.saturating_sub(reserved) < payout {
+        return Err(ContractError::InsufficientReserve {});
+    }
+    Ok(())
 }
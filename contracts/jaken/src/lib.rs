@@ -1,8 +1,20 @@
+pub mod badges;
+pub mod beacon;
 pub mod contract;
 mod error;
 pub mod helpers;
 pub mod msg;
+#[cfg(any(test, feature = "multitest"))]
+pub mod multitest;
+pub mod rating;
+pub mod rules;
 pub mod state;
+pub mod tournament;
 pub mod utils;
+pub mod zk;
 
 pub use crate::error::ContractError;
+
+// Re-exported so that contracts administering jaken don't need a direct
+// dependency on cw_ownable, see `ExecuteMsg::UpdateOwnership`.
+pub use cw_ownable::{Action, Ownership, OwnershipError};
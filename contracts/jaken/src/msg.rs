@@ -1,47 +1,352 @@
-use cosmwasm_std::{Addr, Coin, Response};
-use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Binary, Coin, Int128, Response, Uint128};
+use cw20::Cw20ReceiveMsg;
+use cw721::Cw721ReceiveMsg;
+use cw_utils::Duration;
 
-use crate::state::{Game, GameMove, GameResult};
+use crate::rules::GameKind;
+use crate::state::{
+    BeaconSchedule, Challenge, Game, GameMove, GameResult, PendingBet, PlayerStats, Tournament,
+    TournamentMatch,
+};
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[cw_serde]
 pub struct InstantiateMsg {
+    /// administers jaken, ownership can be transferred later with `UpdateOwnership`
     pub owner: Addr,
     pub prng_seed: String,
+    /// default deadline for each move in PvP games, one day if not set
+    pub game_timeout: Option<Duration>,
+    /// BLS12-381 G2 key of a drand style randomness beacon, enables beacon settled house bets
+    pub beacon_pubkey: Option<Binary>,
+    /// rounds of the beacon, e.g. the genesis and period of a drand network.
+    /// A round every 30 seconds from one period after instantiation if not set.
+    pub beacon_schedule: Option<BeaconSchedule>,
+    /// Groth16 verifying keys for zero-knowledge games, they cannot be changed later
+    pub verifying_keys: Option<Vec<CircuitKey>>,
+    /// basis points of the winnings the house keeps on a winning bet, none if not set
+    pub house_edge_bps: Option<u16>,
+    /// cw721-base collection badges are minted from, jaken has to be its minter
+    pub badge_collection: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[cw_serde]
+pub struct CircuitKey {
+    pub circuit: String,
+    /// compressed ark-serialize encoding of the BN254 verifying key
+    pub verifying_key: Binary,
+}
+
+#[cw_serde]
 pub enum ExecuteMsg {
+    /// Host starts a game with a sha256 commitment to its move (see `utils::commit_move`).
+    /// Funds sent along are escrowed as the game stake.
     StartGame {
-        opponent: String,
-        first_move: GameMove,
+        /// leave empty to post an open challenge to the lobby
+        opponent: Option<String>,
+        commitment: Binary,
+        /// overrides the contract wide `game_timeout` for this game
+        timeout: Option<Duration>,
+        /// settle the game with an outcome proof of this circuit instead of a reveal,
+        /// `commitment` is then a MiMC commitment (see `zk::mimc_commit`)
+        circuit: Option<String>,
+        /// play a best-of-`rounds` series instead of a single throw, must be odd
+        rounds: Option<u32>,
+        /// rule set of the game, rock paper scissors if not set
+        kind: Option<GameKind>,
     },
+    /// Opponent plays its move, sending the same funds as the host stake
     Respond {
         host: String,
         second_move: GameMove,
+        /// accept the open challenge of `host` with this id, or pick one of several
+        /// games `host` started against the sender
+        game_id: Option<u64>,
+    },
+    /// Host opens its commitment once the opponent responded, this settles the game
+    Reveal {
+        opponent: String,
+        first_move: GameMove,
+        salt: String,
+        /// commitment to the move of the next round, required unless this round decides the match
+        next_commitment: Option<Binary>,
+        /// required when the host plays several games against `opponent`
+        game_id: Option<u64>,
+    },
+    /// Host settles a ZK game by proving the outcome without revealing its move
+    ProveOutcome {
+        opponent: String,
+        /// outcome of the current round
+        result: GameResult,
+        proof: Binary,
+        next_commitment: Option<Binary>,
+        /// required when the host plays several games against `opponent`
+        game_id: Option<u64>,
+    },
+    /// Host cancels its game before the opponent responded, the stake is refunded
+    CancelGame {
+        opponent: String,
+        /// required when the host plays several games against `opponent`
+        game_id: Option<u64>,
+    },
+    /// Host takes its open challenge off the lobby, the stake is refunded
+    CancelChallenge {
+        game_id: u64,
     },
 
+    /// Opens registration for a single elimination tournament
+    CreateTournament {
+        entry_fee: Coin,
+        max_players: u32,
+        /// basis points of the prize pool kept by the organizer
+        organizer_cut_bps: Option<u16>,
+        /// basis points for the champion, the finalist and the semifinalists,
+        /// the champion takes it all if not set
+        prize_split: Option<Vec<u16>>,
+        /// overrides the contract wide `game_timeout` for the tournament games
+        timeout: Option<Duration>,
+    },
+    /// Registers the sender, sending the entry fee. The tournament starts once it is full.
+    /// A cw20 entry fee is sent with the token instead, see `ReceiveMsg::RegisterTournament`.
+    RegisterTournament {
+        tournament_id: u64,
+    },
+    /// Organizer starts the tournament before it is full
+    StartTournament {
+        tournament_id: u64,
+    },
+    /// Plays with cw20 tokens, `msg` is a `ReceiveMsg` and the received tokens
+    /// are used as if they were sent along with it
+    Receive(Cw20ReceiveMsg),
+    /// Owner accepts a cw20 token, bets with it still need a `SetBetLimit` for
+    /// its `cw20:<address>` denom
+    AllowCw20 {
+        token: String,
+    },
+    RemoveCw20 {
+        token: String,
+    },
+    /// Stakes an NFT, `msg` is a `NftReceiveMsg` and the received token is escrowed
+    /// as the stake of the game
+    ReceiveNft(Cw721ReceiveMsg),
+    /// Owner accepts a cw721 collection for NFT stakes
+    AllowCollection {
+        collection: String,
+    },
+    RemoveCollection {
+        collection: String,
+    },
+
+    /// Organizer calls off a tournament during registration, entry fees are refunded
+    CancelTournament {
+        tournament_id: u64,
+    },
+    /// First player of a bracket match starts its game, which is then played
+    /// with `Respond` and `Reveal`. Tied games are replayed.
+    StartMatch {
+        tournament_id: u64,
+        commitment: Binary,
+    },
+    /// The second player advances when the first one did not start the match in time
+    ClaimMatch {
+        tournament_id: u64,
+        slot: u32,
+    },
+    /// Either player settles a game where the other side missed the deadline.
+    /// If the opponent never responded the host is refunded, if the host never
+    /// revealed the opponent wins by forfeit. A player leaving a series forfeits it.
+    /// A forfeit only awards the coin stakes, staked NFTs go back to their owners.
+    ClaimTimeout {
+        host: String,
+        opponent: String,
+        /// required when the pair plays several games
+        game_id: Option<u64>,
+    },
+
+    /// Bet against the house. With a beacon configured the bet is settled with
+    /// `SettleBet` once the next beacon round is submitted and `entropy` is ignored.
     BetToken {
         first_move: GameMove,
         entropy: String,
+        /// rule set of the bet, rock paper scissors if not set
+        kind: Option<GameKind>,
+    },
+    /// Submits the beacon signature of the next round, anyone can relay it
+    SubmitBeacon {
+        round: u64,
+        signature: Binary,
+    },
+    /// Settles a pending house bet against its beacon round, anyone can call it
+    SettleBet {
+        bet_id: u64,
     },
 
     Withdraw {
         coin: Coin,
     },
+
+    /// Owner whitelists a denom for `BetToken`, or updates its limits
+    SetBetLimit {
+        denom: String,
+        min_bet: Uint128,
+        max_bet: Uint128,
+    },
+    /// Owner stops accepting bets in a denom
+    RemoveBetLimit {
+        denom: String,
+    },
+    /// Owner sets the house edge, pending bets keep the edge they were placed with
+    SetHouseEdge {
+        house_edge_bps: u16,
+    },
+    /// Owner rotates or removes the beacon key, only while no bet is pending
+    SetBeacon {
+        pubkey: Option<Binary>,
+        /// replaces the round schedule, the current one is kept if not set
+        schedule: Option<BeaconSchedule>,
+    },
+    /// Owner stops or resumes accepting new games, bets and tournaments
+    SetPaused {
+        paused: bool,
+    },
+    /// Owner sets the badge collection, badges stop being minted if it is removed
+    SetBadgeCollection {
+        collection: Option<String>,
+    },
+    /// Owner replaces the seed of the house move generator
+    Reseed {
+        prng_seed: String,
+    },
+    /// Two-step ownership transfer: the owner proposes, the new owner accepts
+    UpdateOwnership(cw_ownable::Action),
+}
+
+/// Payload of a cw20 `Send` to jaken
+#[cw_serde]
+pub enum ReceiveMsg {
+    StartGame {
+        opponent: Option<String>,
+        commitment: Binary,
+        timeout: Option<Duration>,
+        circuit: Option<String>,
+        rounds: Option<u32>,
+        kind: Option<GameKind>,
+    },
+    /// Matches the stake of a game started with the same token
+    Respond {
+        host: String,
+        second_move: GameMove,
+        game_id: Option<u64>,
+    },
+    BetToken {
+        first_move: GameMove,
+        entropy: String,
+        /// rule set of the bet, rock paper scissors if not set
+        kind: Option<GameKind>,
+    },
+    /// Pays the entry fee of a tournament created with the same token
+    RegisterTournament { tournament_id: u64 },
+}
+
+/// Payload of a cw721 `SendNft` to jaken
+#[cw_serde]
+pub enum NftReceiveMsg {
+    StartGame {
+        opponent: Option<String>,
+        commitment: Binary,
+        timeout: Option<Duration>,
+        circuit: Option<String>,
+        rounds: Option<u32>,
+        kind: Option<GameKind>,
+    },
+    /// Matches the NFT stake of a game with a token of any accepted collection
+    Respond {
+        host: String,
+        second_move: GameMove,
+        game_id: Option<u64>,
+    },
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[cw_serde]
+#[derive(QueryResponses)]
 pub enum QueryMsg {
-    // GetCount returns the current count as a json-encoded number
+    /// Result of the last finished game between host and opponent
+    #[returns(ResultResponse)]
     GetResult { host: String, opponent: String },
+    /// Finished games the player took part in, most recent first
+    #[returns(GameResponse)]
+    GamesByPlayer {
+        player: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// All finished games, most recent first
+    #[returns(GameResponse)]
+    RecentGames {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Denoms accepted by `BetToken` and their limits
+    #[returns(BetLimitsResponse)]
+    BetLimits {},
+    /// Beacon configuration and the last submitted round
+    #[returns(BeaconResponse)]
+    Beacon {},
+    #[returns(PendingBet)]
+    PendingBet { bet_id: u64 },
+    /// Circuits with a registered verifying key
+    #[returns(CircuitsResponse)]
+    Circuits {},
+    /// Challenges that can still be accepted, oldest first. Filters by stake denom and,
+    /// along with the denom, amount. An empty denom lists the challenges without any stake.
+    #[returns(OpenChallengesResponse)]
+    OpenChallenges {
+        denom: Option<String>,
+        amount: Option<Uint128>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Rating and win/loss/tie record of a player
+    #[returns(PlayerStats)]
+    PlayerStats { address: String },
+    /// Players ranked by rating, highest first. `start_after` is the address
+    /// of the last player of the previous page.
+    #[returns(LeaderboardResponse)]
+    Leaderboard {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Tournament settings, players and the whole bracket
+    #[returns(TournamentResponse)]
+    Tournament { tournament_id: u64 },
+    /// Tournaments, most recent first
+    #[returns(TournamentsResponse)]
+    Tournaments {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// cw20 contracts accepted for games and bets
+    #[returns(Cw20TokensResponse)]
+    Cw20Tokens {},
+    /// cw721 collections accepted for NFT stakes
+    #[returns(CollectionsResponse)]
+    Collections {},
+    /// House edge and the settled bets of every denom
+    #[returns(HouseStatsResponse)]
+    HouseStats {},
+    /// Whether new games and bets are accepted
+    #[returns(PausedResponse)]
+    Paused {},
+    /// Collection achievement badges are minted from
+    #[returns(BadgeCollectionResponse)]
+    BadgeCollection {},
+    /// Current owner and pending ownership transfer, see `cw_ownable::Ownership`
+    #[returns(cw_ownable::Ownership<Addr>)]
+    Ownership {},
 }
 
 // We define a custom struct for each query response
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[cw_serde]
 pub struct ResultResponse {
     pub result: GameResult,
 }
@@ -55,8 +360,90 @@ impl From<ResultResponse> for Response {
 }
 
 // We define a custom struct for each query response
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[cw_serde]
 pub struct GameResponse {
     pub result: Vec<Game>,
 }
+
+#[cw_serde]
+pub struct BetLimitResponse {
+    pub denom: String,
+    pub min_bet: Uint128,
+    pub max_bet: Uint128,
+}
+
+#[cw_serde]
+pub struct BetLimitsResponse {
+    pub limits: Vec<BetLimitResponse>,
+}
+
+#[cw_serde]
+pub struct BeaconResponse {
+    pub pubkey: Option<Binary>,
+    pub latest_round: u64,
+    pub schedule: Option<BeaconSchedule>,
+}
+
+#[cw_serde]
+pub struct CircuitsResponse {
+    pub circuits: Vec<String>,
+}
+
+#[cw_serde]
+pub struct OpenChallengesResponse {
+    pub challenges: Vec<Challenge>,
+}
+
+#[cw_serde]
+pub struct LeaderboardResponse {
+    pub players: Vec<PlayerStats>,
+}
+
+#[cw_serde]
+pub struct TournamentResponse {
+    pub tournament: Tournament,
+    /// matches of all rounds played so far, by round and slot
+    pub matches: Vec<TournamentMatch>,
+}
+
+#[cw_serde]
+pub struct TournamentsResponse {
+    pub tournaments: Vec<Tournament>,
+}
+
+#[cw_serde]
+pub struct Cw20TokensResponse {
+    pub tokens: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct CollectionsResponse {
+    pub collections: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct DenomStatsResponse {
+    pub denom: String,
+    pub wagered: Uint128,
+    pub paid_out: Uint128,
+    /// wagered minus paid out, negative while players are ahead
+    pub net_profit: Int128,
+    /// funds held for escrowed stakes and the worst case of pending bets
+    pub reserved: Uint128,
+}
+
+#[cw_serde]
+pub struct HouseStatsResponse {
+    pub house_edge_bps: u16,
+    pub stats: Vec<DenomStatsResponse>,
+}
+
+#[cw_serde]
+pub struct PausedResponse {
+    pub paused: bool,
+}
+
+#[cw_serde]
+pub struct BadgeCollectionResponse {
+    pub collection: Option<Addr>,
+}
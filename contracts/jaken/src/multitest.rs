@@ -0,0 +1,253 @@
+use cosmwasm_std::{coins, Addr, CosmosMsg, Empty, Uint128};
+use cw_multi_test::error::AnyResult;
+use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+
+use crate::contract::{execute, instantiate, migrate, query, reply};
+use crate::helpers::CwTemplateContract;
+use crate::msg::{ExecuteMsg, InstantiateMsg};
+
+/// Denom the fixture funds the house and the players with
+pub const DENOM: &str = "ujaken";
+/// Bet limits `DENOM` is accepted with by the fixture
+pub const MIN_BET: u128 = 1;
+pub const MAX_BET: u128 = 1_000_000;
+
+pub fn jaken_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(execute, instantiate, query)
+        .with_reply(reply)
+        .with_migrate(migrate);
+    Box::new(contract)
+}
+
+/// jaken deployed on a multi-test `App` with a funded bank
+pub struct JakenFixture {
+    pub app: App,
+    pub jaken: CwTemplateContract,
+    pub owner: Addr,
+}
+
+impl JakenFixture {
+    /// Deploys jaken with a bankroll of `house` `DENOM` and gives each of `players`
+    /// `funds` `DENOM`. Players are addressed by name, see `addr`.
+    pub fn new(house: u128, players: &[&str], funds: u128) -> Self {
+        let mut app = App::default();
+        let owner = app.api().addr_make("owner");
+
+        let code_id = app.store_code(jaken_contract());
+        let msg = InstantiateMsg {
+            owner: owner.clone(),
+            prng_seed: "jaken".to_string(),
+            game_timeout: None,
+            beacon_pubkey: None,
+            beacon_schedule: None,
+            verifying_keys: None,
+            house_edge_bps: None,
+            badge_collection: None,
+        };
+        let jaken = app
+            .instantiate_contract(
+                code_id,
+                owner.clone(),
+                &msg,
+                &[],
+                "jaken",
+                Some(owner.to_string()),
+            )
+            .unwrap();
+
+        app.init_modules(|router, api, storage| {
+            router
+                .bank
+                .init_balance(storage, &jaken, coins(house, DENOM))
+                .unwrap();
+            for player in players {
+                router
+                    .bank
+                    .init_balance(storage, &api.addr_make(player), coins(funds, DENOM))
+                    .unwrap();
+            }
+        });
+
+        let jaken = CwTemplateContract(jaken);
+        let msg = ExecuteMsg::SetBetLimit {
+            denom: DENOM.to_string(),
+            min_bet: Uint128::new(MIN_BET),
+            max_bet: Uint128::new(MAX_BET),
+        };
+        app.execute(owner.clone(), jaken.call(msg).unwrap())
+            .unwrap();
+
+        JakenFixture { app, jaken, owner }
+    }
+
+    pub fn addr(&self, name: &str) -> Addr {
+        self.app.api().addr_make(name)
+    }
+
+    /// Runs a message built with the `CwTemplateContract` helpers
+    pub fn execute(&mut self, sender: &Addr, msg: CosmosMsg) -> AnyResult<AppResponse> {
+        self.app.execute(sender.clone(), msg)
+    }
+
+    pub fn balance(&self, address: &Addr) -> u128 {
+        self.app
+            .wrap()
+            .query_balance(address, DENOM)
+            .unwrap()
+            .amount
+            .u128()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msg::{ExecuteMsg, HouseStatsResponse, LeaderboardResponse, QueryMsg};
+    use crate::state::{GameMove, GameResult};
+    use crate::utils::commit_move;
+    use crate::ContractError;
+    use cosmwasm_std::{coin, Binary, Event};
+
+    const SALT: &str = "pepper";
+
+    #[test]
+    fn staked_game_moves_funds() {
+        let mut fixture = JakenFixture::new(0, &["host", "opponent"], 1_000);
+        let host = fixture.addr("host");
+        let opponent = fixture.addr("opponent");
+        let jaken = fixture.jaken.clone();
+
+        let commitment = Binary::from(commit_move(&GameMove::Rock {}, SALT));
+        let msg = jaken
+            .start_game(Some(opponent.to_string()), commitment, coins(100, DENOM))
+            .unwrap();
+        fixture.execute(&host, msg).unwrap();
+        assert_eq!(900, fixture.balance(&host));
+        assert_eq!(100, fixture.balance(&jaken.addr()));
+
+        // the opponent has to match the stake
+        let msg = jaken
+            .respond(
+                host.to_string(),
+                GameMove::Scissors {},
+                None,
+                coins(50, DENOM),
+            )
+            .unwrap();
+        let err = fixture.execute(&opponent, msg).unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::StakeMismatch {}
+        ));
+        let msg = jaken
+            .respond(
+                host.to_string(),
+                GameMove::Scissors {},
+                None,
+                coins(100, DENOM),
+            )
+            .unwrap();
+        fixture.execute(&opponent, msg).unwrap();
+
+        let msg = jaken
+            .reveal(opponent.to_string(), GameMove::Rock {}, SALT.to_string())
+            .unwrap();
+        fixture.execute(&host, msg).unwrap();
+        assert_eq!(1_100, fixture.balance(&host));
+        assert_eq!(900, fixture.balance(&opponent));
+        assert_eq!(0, fixture.balance(&jaken.addr()));
+
+        let res = jaken
+            .result(&fixture.app.wrap(), host.to_string(), opponent.to_string())
+            .unwrap();
+        assert_eq!(GameResult::HostWins {}, res.result);
+    }
+
+    #[test]
+    fn failed_badge_mint_keeps_the_game() {
+        let mut fixture = JakenFixture::new(0, &["host", "opponent"], 1_000);
+        let host = fixture.addr("host");
+        let opponent = fixture.addr("opponent");
+        let owner = fixture.owner.clone();
+        let jaken = fixture.jaken.clone();
+
+        // no contract lives at the collection address, every mint fails
+        let msg = jaken
+            .call(ExecuteMsg::SetBadgeCollection {
+                collection: Some(fixture.addr("collection").to_string()),
+            })
+            .unwrap();
+        fixture.execute(&owner, msg).unwrap();
+
+        let commitment = Binary::from(commit_move(&GameMove::Rock {}, SALT));
+        let msg = jaken
+            .start_game(Some(opponent.to_string()), commitment, coins(100, DENOM))
+            .unwrap();
+        fixture.execute(&host, msg).unwrap();
+        let msg = jaken
+            .respond(
+                host.to_string(),
+                GameMove::Scissors {},
+                None,
+                coins(100, DENOM),
+            )
+            .unwrap();
+        fixture.execute(&opponent, msg).unwrap();
+
+        let msg = jaken
+            .reveal(opponent.to_string(), GameMove::Rock {}, SALT.to_string())
+            .unwrap();
+        let res = fixture.execute(&host, msg).unwrap();
+        assert!(res.has_event(&Event::new("wasm").add_attribute("action", "badge_mint_failed")));
+
+        // the game is settled and rated all the same
+        assert_eq!(1_100, fixture.balance(&host));
+        assert_eq!(900, fixture.balance(&opponent));
+        let leaderboard: LeaderboardResponse = jaken
+            .query(
+                &fixture.app.wrap(),
+                QueryMsg::Leaderboard {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(host, leaderboard.players[0].address);
+        assert_eq!(1, leaderboard.players[0].wins);
+    }
+
+    #[test]
+    fn bets_and_withdraw() {
+        let mut fixture = JakenFixture::new(10_000, &["player"], 1_000);
+        let player = fixture.addr("player");
+        let owner = fixture.owner.clone();
+        let jaken = fixture.jaken.clone();
+
+        let msg = jaken
+            .bet(GameMove::Paper {}, "entropy".to_string(), coin(100, DENOM))
+            .unwrap();
+        fixture.execute(&player, msg).unwrap();
+
+        // whatever the house threw, the bet moved between the player and the house
+        let won = fixture.balance(&player);
+        assert!([900, 1_000, 1_100].contains(&won));
+        assert_eq!(11_000, won + fixture.balance(&jaken.addr()));
+        let stats: HouseStatsResponse = jaken
+            .query(&fixture.app.wrap(), QueryMsg::HouseStats {})
+            .unwrap();
+        assert_eq!(Uint128::new(100), stats.stats[0].wagered);
+
+        // only the owner withdraws, and never more than the house holds
+        let msg = jaken.withdraw(coin(500, DENOM)).unwrap();
+        fixture.execute(&player, msg.clone()).unwrap_err();
+        fixture.execute(&owner, msg).unwrap();
+        assert_eq!(500, fixture.balance(&owner));
+
+        let msg = jaken.withdraw(coin(20_000, DENOM)).unwrap();
+        let err = fixture.execute(&owner, msg).unwrap_err();
+        assert!(matches!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::InsufficientReserve {}
+        ));
+    }
+}
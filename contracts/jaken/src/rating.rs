@@ -0,0 +1,37 @@
+/// Rating of a player before its first finished game
+pub const INITIAL_RATING: u32 = 1200;
+
+/// Maximum rating change of a single game
+pub const K_FACTOR: i64 = 32;
+
+/// Game scores in per mille
+pub const WIN: i64 = 1000;
+pub const TIE: i64 = 500;
+pub const LOSS: i64 = 0;
+
+/// Expected score in per mille of the stronger player for rating differences
+/// of 0, 25, .., 400 points, `1000 / (1 + 10^(-diff / 400))` rounded.
+/// Larger differences are capped at 400 like in the FIDE tables.
+const EXPECTED_SCORE: [i64; 17] = [
+    500, 536, 571, 606, 640, 673, 703, 733, 760, 785, 808, 830, 849, 867, 882, 896, 909,
+];
+
+/// Expected score in per mille of a player rated `rating` against `opponent`
+pub fn expected_score(rating: u32, opponent: u32) -> i64 {
+    let diff = (i64::from(rating) - i64::from(opponent)).clamp(-400, 400);
+    let expected = EXPECTED_SCORE[(diff.unsigned_abs() / 25) as usize];
+    match diff < 0 {
+        true => 1000 - expected,
+        false => expected,
+    }
+}
+
+/// Rating points won (or lost, if negative) by a player scoring `score` against
+/// `opponent`. The opponent's rating changes by the same amount the other way.
+pub fn rating_change(rating: u32, opponent: u32, score: i64) -> i64 {
+    K_FACTOR * (score - expected_score(rating, opponent)) / 1000
+}
+
+pub fn apply_change(rating: u32, change: i64) -> u32 {
+    (i64::from(rating) + change).clamp(0, i64::from(u32::MAX)) as u32
+}
@@ -0,0 +1,122 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ContractError;
+use crate::state::GameMove;
+use crate::utils::sha_256;
+
+const RPS_MOVES: [GameMove; 3] = [GameMove::Rock {}, GameMove::Paper {}, GameMove::Scissors {}];
+
+const RPSLS_MOVES: [GameMove; 5] = [
+    GameMove::Rock {},
+    GameMove::Paper {},
+    GameMove::Scissors {},
+    GameMove::Lizard {},
+    GameMove::Spock {},
+];
+
+/// (winner, loser) pairs of rock paper scissors
+const RPS_BEATS: [(GameMove, GameMove); 3] = [
+    (GameMove::Rock {}, GameMove::Scissors {}),
+    (GameMove::Paper {}, GameMove::Rock {}),
+    (GameMove::Scissors {}, GameMove::Paper {}),
+];
+
+/// (winner, loser) pairs of rock paper scissors lizard spock, each move beats two others
+const RPSLS_BEATS: [(GameMove, GameMove); 10] = [
+    (GameMove::Rock {}, GameMove::Scissors {}),
+    (GameMove::Rock {}, GameMove::Lizard {}),
+    (GameMove::Paper {}, GameMove::Rock {}),
+    (GameMove::Paper {}, GameMove::Spock {}),
+    (GameMove::Scissors {}, GameMove::Paper {}),
+    (GameMove::Scissors {}, GameMove::Lizard {}),
+    (GameMove::Lizard {}, GameMove::Paper {}),
+    (GameMove::Lizard {}, GameMove::Spock {}),
+    (GameMove::Spock {}, GameMove::Rock {}),
+    (GameMove::Spock {}, GameMove::Scissors {}),
+];
+
+/// Rule set a game is played with, chosen by the host or the betting player
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GameKind {
+    /// rock paper scissors
+    #[default]
+    Rps,
+    /// rock paper scissors lizard spock
+    Rpsls,
+}
+
+impl Display for GameKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameKind::Rps => write!(f, "rps"),
+            GameKind::Rpsls => write!(f, "rpsls"),
+        }
+    }
+}
+
+impl GameKind {
+    pub fn moves(&self) -> &'static [GameMove] {
+        match self {
+            GameKind::Rps => &RPS_MOVES,
+            GameKind::Rpsls => &RPSLS_MOVES,
+        }
+    }
+
+    fn beats_relation(&self) -> &'static [(GameMove, GameMove)] {
+        match self {
+            GameKind::Rps => &RPS_BEATS,
+            GameKind::Rpsls => &RPSLS_BEATS,
+        }
+    }
+
+    pub fn validate_move(&self, game_move: &GameMove) -> Result<(), ContractError> {
+        match self.moves().contains(game_move) {
+            true => Ok(()),
+            false => Err(ContractError::InvalidMove {
+                game_move: game_move.clone(),
+                kind: *self,
+            }),
+        }
+    }
+
+    pub fn beats(&self, winner: &GameMove, loser: &GameMove) -> bool {
+        self.beats_relation()
+            .iter()
+            .any(|(w, l)| w == winner && l == loser)
+    }
+
+    /// Resolves a throw for PvP and house games alike, `Greater` if `first` wins
+    pub fn resolve(&self, first: &GameMove, second: &GameMove) -> Ordering {
+        if self.beats(first, second) {
+            Ordering::Greater
+        } else if self.beats(second, first) {
+            Ordering::Less
+        } else {
+            Ordering::Equal
+        }
+    }
+
+    /// House move drawn uniformly from the move set with the first random byte below
+    /// the largest multiple of the move count, the bytes above it would favour the first moves.
+    /// When every byte is above it the draw goes on with the hash of the bytes.
+    pub fn rand_move(&self, rng: &[u8]) -> GameMove {
+        let moves = self.moves();
+        let bound = 256 - 256 % moves.len();
+        let mut bytes = rng.to_vec();
+        loop {
+            if let Some(byte) = bytes
+                .iter()
+                .map(|byte| *byte as usize)
+                .find(|byte| *byte < bound)
+            {
+                return moves[byte % moves.len()].clone();
+            }
+            bytes = sha_256(&bytes).to_vec();
+        }
+    }
+}
@@ -3,22 +3,107 @@ use std::fmt::Display;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::Addr;
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Binary, Coin, Empty, StdResult, Storage, Timestamp, Uint128};
+use cw_ownable::Ownership;
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_utils::{Duration, Expiration};
+
+use crate::rating::INITIAL_RATING;
+use crate::rules::GameKind;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 
 pub struct State {
+    /// default time a player has to make its next move before the game can be claimed
+    pub game_timeout: Duration,
+    /// BLS12-381 G2 key of the randomness beacon, house bets wait for a beacon round when set
+    pub beacon_pubkey: Option<Binary>,
+    /// share of a winning bet's winnings kept by the house, in basis points
+    pub house_edge_bps: u16,
+    /// new games and bets are refused while paused, running ones can still finish
+    #[serde(default)]
+    pub paused: bool,
+    /// cw721-base collection jaken mints achievement badges from, jaken has to be its minter
+    #[serde(default)]
+    pub badge_collection: Option<Addr>,
+}
+
+/// `State` of the first jaken release, see `migrate`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LegacyState {
     pub owner: Addr,
 }
 
+/// `Game` of the first jaken release, see `migrate`. Games were started without funds.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LegacyGame {
+    pub host: Addr,
+    pub opponent: Addr,
+    pub host_move: GameMove,
+    pub opp_move: Option<GameMove>,
+    pub result: Option<GameResult>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Game {
+    /// assigned when the game starts, finished games are archived under it
+    pub id: u64,
     pub host: Addr,
     pub opponent: Addr,
-    pub host_move: GameMove,
+    /// sha256 commitment to the host move, see `utils::commit_move`,
+    /// or a MiMC commitment for games settled with a proof of `circuit`
+    pub host_commitment: Binary,
+    /// verifying key the outcome proof is checked against, see `zk`
+    pub circuit: Option<String>,
+    /// rule set both players' moves are checked against
+    #[serde(default)]
+    pub kind: GameKind,
+    /// only known once the host has revealed the commitment
+    pub host_move: Option<GameMove>,
     pub opp_move: Option<GameMove>,
+    /// only set once a player wins the majority of the rounds
     pub result: Option<GameResult>,
+    /// the match is played best-of-`rounds`
+    pub rounds: u32,
+    /// round being played, tied rounds of a series are replayed as the next round
+    pub round: u32,
+    pub host_score: u32,
+    pub opp_score: u32,
+    /// funds escrowed by each player, the opponent has to match the host stake
+    pub stake: Option<Coin>,
+    /// token escrowed by the host instead of funds
+    #[serde(default)]
+    pub host_nft: Option<Nft>,
+    /// token the opponent matched the host token with
+    #[serde(default)]
+    pub opp_nft: Option<Nft>,
+    /// time each player gets for its next move
+    pub timeout: Duration,
+    /// deadline for the next move, refreshed when the opponent responds
+    pub expires: Expiration,
+    /// bracket match the game decides
+    pub tournament: Option<MatchRef>,
+}
+
+/// cw721 token escrowed as a game stake
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Nft {
+    pub collection: Addr,
+    pub token_id: String,
+}
+
+impl Display for Nft {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.collection, self.token_id)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MatchRef {
+    pub tournament_id: u64,
+    pub round: u32,
+    pub slot: u32,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -27,6 +112,10 @@ pub enum GameMove {
     Rock {},
     Paper {},
     Scissors {},
+    /// only in `GameKind::Rpsls` games
+    Lizard {},
+    /// only in `GameKind::Rpsls` games
+    Spock {},
 }
 
 impl Display for GameMove {
@@ -35,20 +124,12 @@ impl Display for GameMove {
             GameMove::Rock {} => write!(f, "Rock"),
             GameMove::Paper {} => write!(f, "Paper"),
             GameMove::Scissors {} => write!(f, "Scissors"),
+            GameMove::Lizard {} => write!(f, "Lizard"),
+            GameMove::Spock {} => write!(f, "Spock"),
         }
     }
 }
 
-pub fn rand_move(rng: &[u8]) -> GameMove {
-    let num: u8 = rng[0] % 3 + 1;
-    match num {
-        1 => GameMove::Rock {},
-        2 => GameMove::Paper {},
-        3 => GameMove::Scissors {},
-        _ => panic!("Invalid random number"),
-    }
-}
-
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub enum GameResult {
     HostWins {},
@@ -70,6 +151,298 @@ impl Display for GameResult {
     }
 }
 
+pub fn next_game_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = GAME_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    GAME_COUNT.save(storage, &id)?;
+    Ok(id)
+}
+
+pub struct GameIndexes<'a> {
+    /// host and opponent, a pair can play several games at once
+    pub pair: MultiIndex<'a, (Addr, Addr), Game, u64>,
+}
+
+impl<'a> IndexList<Game> for GameIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Game>> + '_> {
+        let v: Vec<&dyn Index<Game>> = vec![&self.pair];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Games being played by id, finished games move to `GAMES`
+pub fn active_games<'a>() -> IndexedMap<u64, Game, GameIndexes<'a>> {
+    let indexes = GameIndexes {
+        pair: MultiIndex::new(
+            |_pk, d: &Game| (d.host.clone(), d.opponent.clone()),
+            "active_games",
+            "active_games__pair",
+        ),
+    };
+    IndexedMap::new("active_games", indexes)
+}
+
+/// Keeps a finished game around for `GetResult` and the history queries
+pub fn archive_game(storage: &mut dyn Storage, game: &Game) -> StdResult<()> {
+    GAMES.save(storage, game.id, game)?;
+    PLAYER_GAMES.save(storage, (game.host.clone(), game.id), &Empty {})?;
+    PLAYER_GAMES.save(storage, (game.opponent.clone(), game.id), &Empty {})?;
+    LAST_GAME.save(
+        storage,
+        (game.host.clone(), game.opponent.clone()),
+        &game.id,
+    )
+}
+
+/// A game posted to the lobby without an opponent, the first player to respond takes it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Challenge {
+    pub id: u64,
+    pub host: Addr,
+    pub host_commitment: Binary,
+    pub circuit: Option<String>,
+    #[serde(default)]
+    pub kind: GameKind,
+    pub rounds: u32,
+    pub stake: Option<Coin>,
+    #[serde(default)]
+    pub host_nft: Option<Nft>,
+    pub timeout: Duration,
+    /// the challenge can no longer be accepted after this
+    pub expires: Expiration,
+}
+
+impl Challenge {
+    /// The game played once `opponent` accepts the challenge
+    pub fn into_game(self, opponent: Addr) -> Game {
+        Game {
+            id: self.id,
+            host: self.host,
+            opponent,
+            host_commitment: self.host_commitment,
+            circuit: self.circuit,
+            kind: self.kind,
+            host_move: None,
+            opp_move: None,
+            result: None,
+            rounds: self.rounds,
+            round: 1,
+            host_score: 0,
+            opp_score: 0,
+            stake: self.stake,
+            host_nft: self.host_nft,
+            opp_nft: None,
+            timeout: self.timeout,
+            expires: self.expires,
+            tournament: None,
+        }
+    }
+}
+
+pub struct ChallengeIndexes<'a> {
+    /// stake denom, empty for unstaked challenges
+    pub denom: MultiIndex<'a, String, Challenge, u64>,
+    /// stake denom and amount
+    pub stake: MultiIndex<'a, (String, u128), Challenge, u64>,
+}
+
+impl<'a> IndexList<Challenge> for ChallengeIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Challenge>> + '_> {
+        let v: Vec<&dyn Index<Challenge>> = vec![&self.denom, &self.stake];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn challenge_stake_idx(_pk: &[u8], d: &Challenge) -> (String, u128) {
+    match &d.stake {
+        Some(stake) => (stake.denom.clone(), stake.amount.u128()),
+        None => (String::new(), 0),
+    }
+}
+
+/// Open challenges by game id
+pub fn challenges<'a>() -> IndexedMap<u64, Challenge, ChallengeIndexes<'a>> {
+    let indexes = ChallengeIndexes {
+        denom: MultiIndex::new(
+            |pk, d| challenge_stake_idx(pk, d).0,
+            "challenges",
+            "challenges__denom",
+        ),
+        stake: MultiIndex::new(challenge_stake_idx, "challenges", "challenges__stake"),
+    };
+    IndexedMap::new("challenges", indexes)
+}
+
+/// Rating and record of a player over its finished PvP games
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PlayerStats {
+    pub address: Addr,
+    pub rating: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub ties: u32,
+    /// consecutive wins up to the last finished game
+    #[serde(default)]
+    pub streak: u32,
+}
+
+pub struct PlayerIndexes<'a> {
+    pub rating: MultiIndex<'a, u32, PlayerStats, Addr>,
+}
+
+impl<'a> IndexList<PlayerStats> for PlayerIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<PlayerStats>> + '_> {
+        let v: Vec<&dyn Index<PlayerStats>> = vec![&self.rating];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Player stats by address, ranked by rating
+pub fn players<'a>() -> IndexedMap<Addr, PlayerStats, PlayerIndexes<'a>> {
+    let indexes = PlayerIndexes {
+        rating: MultiIndex::new(
+            |_pk, d: &PlayerStats| d.rating,
+            "players",
+            "players__rating",
+        ),
+    };
+    IndexedMap::new("players", indexes)
+}
+
+/// Stats of a player, players without a finished game start at the initial rating
+pub fn player_stats(storage: &dyn Storage, address: &Addr) -> StdResult<PlayerStats> {
+    Ok(players()
+        .may_load(storage, address.clone())?
+        .unwrap_or(PlayerStats {
+            address: address.clone(),
+            rating: INITIAL_RATING,
+            wins: 0,
+            losses: 0,
+            ties: 0,
+            streak: 0,
+        }))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TournamentStatus {
+    Registration {},
+    InProgress {},
+    Finished {},
+    Cancelled {},
+}
+
+/// Single elimination bracket, entry fees make up the prize pool
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Tournament {
+    pub id: u64,
+    pub organizer: Addr,
+    pub entry_fee: Coin,
+    pub max_players: u32,
+    /// share of the prize pool paid to the organizer, in basis points
+    pub organizer_cut_bps: u16,
+    /// shares of the rest of the pool in basis points for the champion, the finalist
+    /// and the semifinalists, unclaimed shares go to the champion
+    pub prize_split: Vec<u16>,
+    /// in registration order, which is also the seeding of the first round
+    pub players: Vec<Addr>,
+    pub status: TournamentStatus,
+    /// round being played, 0 during registration
+    pub round: u32,
+    /// time a player has to start its match and for each move
+    pub timeout: Duration,
+    pub champion: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TournamentMatch {
+    pub round: u32,
+    pub slot: u32,
+    /// hosts the game
+    pub player1: Addr,
+    /// `None` is a bye, `player1` advances without playing
+    pub player2: Option<Addr>,
+    pub game_id: Option<u64>,
+    pub winner: Option<Addr>,
+    /// `player1` has to start the game before this or forfeits the match
+    pub expires: Expiration,
+}
+
+pub fn next_tournament_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = TOURNAMENT_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    TOURNAMENT_COUNT.save(storage, &id)?;
+    Ok(id)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BetLimit {
+    pub min_bet: Uint128,
+    pub max_bet: Uint128,
+}
+
+/// When the beacon publishes its rounds: round 1 at `genesis`, then one every `period`
+/// seconds, the way drand networks are scheduled
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BeaconSchedule {
+    pub genesis: Timestamp,
+    pub period: u64,
+}
+
+/// A house bet waiting for the beacon round it will be settled against
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingBet {
+    pub id: u64,
+    pub player: Addr,
+    pub player_move: GameMove,
+    #[serde(default)]
+    pub kind: GameKind,
+    pub bet: Coin,
+    pub round: u64,
+    /// house edge when the bet was placed
+    pub house_edge_bps: u16,
+}
+
+/// Totals of the settled house bets in one denom
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct HouseStats {
+    pub wagered: Uint128,
+    pub paid_out: Uint128,
+}
+
+pub fn record_house_bet(storage: &mut dyn Storage, bet: &Coin, payout: Uint128) -> StdResult<()> {
+    let mut stats = HOUSE_STATS
+        .may_load(storage, &bet.denom)?
+        .unwrap_or_default();
+    stats.wagered = stats.wagered.checked_add(bet.amount)?;
+    stats.paid_out = stats.paid_out.checked_add(payout)?;
+    HOUSE_STATS.save(storage, &bet.denom, &stats)
+}
+
+pub fn next_bet_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = BET_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    BET_COUNT.save(storage, &id)?;
+    Ok(id)
+}
+
+pub fn next_badge_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = BADGE_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    BADGE_COUNT.save(storage, &id)?;
+    Ok(id)
+}
+
+/// Funds the contract holds on behalf of players, e.g. escrowed PvP stakes.
+/// They are part of the contract balance but can never back a house bet.
+pub fn reserve_funds(storage: &mut dyn Storage, coin: &Coin) -> StdResult<Uint128> {
+    RESERVED.update(storage, &coin.denom, |reserved| {
+        Ok(reserved.unwrap_or_default().checked_add(coin.amount)?)
+    })
+}
+
+pub fn release_funds(storage: &mut dyn Storage, coin: &Coin) -> StdResult<Uint128> {
+    RESERVED.update(storage, &coin.denom, |reserved| {
+        Ok(reserved.unwrap_or_default().checked_sub(coin.amount)?)
+    })
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Random {
     pub prng_seed: Vec<u8>,
@@ -77,5 +450,41 @@ pub struct Random {
 }
 
 pub const STATE: Item<State> = Item::new("state");
-pub const GAME: Map<(Addr, Addr), Game> = Map::new("game");
+pub const LEGACY_STATE: Item<LegacyState> = Item::new("state");
+/// games of the first jaken release by host and opponent, dropped by `migrate`
+pub const LEGACY_GAMES: Map<(Addr, Addr), LegacyGame> = Map::new("game");
+/// where cw-ownable keeps the owner, only read by `migrate` as the first release had none
+pub const OWNERSHIP: Item<Ownership<Addr>> = Item::new("ownership");
+pub const GAME_COUNT: Item<u64> = Item::new("game_count");
+/// finished games by id
+pub const GAMES: Map<u64, Game> = Map::new("games");
+/// ids of the finished games of each player, for both host and opponent
+pub const PLAYER_GAMES: Map<(Addr, u64), Empty> = Map::new("player_games");
+/// id of the last finished game between a host and an opponent
+pub const LAST_GAME: Map<(Addr, Addr), u64> = Map::new("last_game");
 pub const RANDOM: Item<Random> = Item::new("random");
+/// whitelisted denoms for `BetToken` and their limits
+pub const BET_LIMITS: Map<&str, BetLimit> = Map::new("bet_limits");
+pub const RESERVED: Map<&str, Uint128> = Map::new("reserved");
+pub const BET_COUNT: Item<u64> = Item::new("bet_count");
+pub const PENDING_BETS: Map<u64, PendingBet> = Map::new("pending_bets");
+/// Groth16 verifying keys of the outcome circuit, registered at instantiate
+pub const VERIFYING_KEYS: Map<&str, Binary> = Map::new("verifying_keys");
+/// last submitted beacon round, rounds are submitted in order
+pub const LATEST_ROUND: Item<u64> = Item::new("latest_round");
+/// round schedule of the beacon, see `beacon::round_time`
+pub const BEACON_SCHEDULE: Item<BeaconSchedule> = Item::new("beacon_schedule");
+/// randomness derived from each submitted beacon round
+pub const BEACON_ROUNDS: Map<u64, Binary> = Map::new("beacon_rounds");
+pub const TOURNAMENT_COUNT: Item<u64> = Item::new("tournament_count");
+pub const TOURNAMENTS: Map<u64, Tournament> = Map::new("tournaments");
+/// bracket matches by tournament, round and slot
+pub const TOURNAMENT_MATCHES: Map<(u64, u32, u32), TournamentMatch> =
+    Map::new("tournament_matches");
+/// cw20 contracts accepted through `Receive`
+pub const ACCEPTED_CW20: Map<&Addr, Empty> = Map::new("accepted_cw20");
+/// cw721 collections accepted as game stakes through `ReceiveNft`
+pub const ACCEPTED_CW721: Map<&Addr, Empty> = Map::new("accepted_cw721");
+pub const HOUSE_STATS: Map<&str, HouseStats> = Map::new("house_stats");
+/// badge NFTs minted so far, the count is the token id of the last badge
+pub const BADGE_COUNT: Item<u64> = Item::new("badge_count");
@@ -0,0 +1,66 @@
+use cosmwasm_std::{Addr, Uint128};
+
+use crate::error::ContractError;
+
+pub const MAX_PLAYERS: u32 = 64;
+
+/// Basis points of a whole prize pool
+pub const TOTAL_BPS: u16 = 10_000;
+
+pub fn validate_settings(
+    max_players: u32,
+    organizer_cut_bps: u16,
+    prize_split: &[u16],
+) -> Result<(), ContractError> {
+    if !(2..=MAX_PLAYERS).contains(&max_players) {
+        return Err(ContractError::InvalidMaxPlayers {
+            max_players: MAX_PLAYERS,
+        });
+    }
+    if organizer_cut_bps > TOTAL_BPS {
+        return Err(ContractError::InvalidOrganizerCut {});
+    }
+    // champion, finalist and semifinalists
+    let total: u32 = prize_split.iter().map(|share| u32::from(*share)).sum();
+    if prize_split.is_empty() || prize_split.len() > 3 || total != u32::from(TOTAL_BPS) {
+        return Err(ContractError::InvalidPrizeSplit {});
+    }
+    Ok(())
+}
+
+/// Pairs neighbouring players, the last one gets a bye when the count is odd
+pub fn pair_players(players: &[Addr]) -> Vec<(Addr, Option<Addr>)> {
+    players
+        .chunks(2)
+        .map(|pair| (pair[0].clone(), pair.get(1).cloned()))
+        .collect()
+}
+
+/// Splits the prize pool between the organizer and the finishers, `places` lists the
+/// champion, the finalist and the semifinalists. Places that share a prize split it
+/// evenly, rounding leftovers and unclaimed shares go to the champion.
+pub fn prize_payouts(
+    pot: Uint128,
+    organizer_cut_bps: u16,
+    prize_split: &[u16],
+    places: &[Vec<Addr>],
+) -> (Uint128, Vec<(Addr, Uint128)>) {
+    let cut = pot.multiply_ratio(organizer_cut_bps, TOTAL_BPS);
+    let prize = pot - cut;
+
+    let mut payouts = vec![];
+    let mut paid = Uint128::zero();
+    for (share, players) in prize_split.iter().zip(places).skip(1) {
+        if players.is_empty() {
+            continue;
+        }
+        let amount = prize.multiply_ratio(*share, TOTAL_BPS) / Uint128::from(players.len() as u128);
+        for player in players {
+            payouts.push((player.clone(), amount));
+            paid += amount;
+        }
+    }
+    payouts.insert(0, (places[0][0].clone(), prize - paid));
+
+    (cut, payouts)
+}
@@ -2,6 +2,8 @@ use rand_chacha::ChaChaRng;
 use rand_core::{RngCore, SeedableRng};
 use sha2::{Digest, Sha256};
 
+use crate::state::GameMove;
+
 pub const SHA256_HASH_SIZE: usize = 32;
 
 pub fn sha_256(data: &[u8]) -> [u8; SHA256_HASH_SIZE] {
@@ -13,6 +15,13 @@ pub fn sha_256(data: &[u8]) -> [u8; SHA256_HASH_SIZE] {
     result.copy_from_slice(hash.as_slice());
     result
 }
+
+/// Commitment the host submits with `StartGame` and opens with `Reveal`.
+/// The salt must stay secret until the reveal, otherwise the move can be brute forced.
+pub fn commit_move(game_move: &GameMove, salt: &str) -> [u8; SHA256_HASH_SIZE] {
+    sha_256(format!("{}:{}", game_move, salt).as_bytes())
+}
+
 pub struct Prng {
     rng: ChaChaRng,
 }
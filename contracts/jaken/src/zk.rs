@@ -0,0 +1,182 @@
+// Zero-knowledge settlement of PvP games.
+//
+// The host commits to its move with `commitment = mimc(host_move, salt) + host_move + salt`
+// over the BN254 scalar field and settles the game with a Groth16 proof of the
+// `OutcomeCircuit` statement: the committed move against the public opponent move
+// gives the claimed outcome. The move itself is never revealed.
+//
+// Public inputs, in order: `[commitment, opponent_move, outcome]` with moves encoded
+// as Rock = 0, Paper = 1, Scissors = 2 and outcomes as Tie = 0, HostWins = 1, OpponentWins = 2.
+use ark_bn254::{Bn254, Fr};
+use ark_ff::{Field, One, PrimeField, Zero};
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, VerifyingKey};
+use ark_relations::lc;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+use crate::error::ContractError;
+use crate::state::{GameMove, GameResult};
+use crate::utils::sha_256;
+
+pub const MIMC_ROUNDS: usize = 91;
+
+/// Size of a compressed BN254 scalar, commitments are sent in this encoding
+pub const FIELD_SIZE: usize = 32;
+
+/// Only rock paper scissors moves have a circuit encoding
+pub fn move_index(game_move: &GameMove) -> Option<u64> {
+    match game_move {
+        GameMove::Rock {} => Some(0),
+        GameMove::Paper {} => Some(1),
+        GameMove::Scissors {} => Some(2),
+        _ => None,
+    }
+}
+
+pub fn outcome_index(result: &GameResult) -> Option<u64> {
+    match result {
+        GameResult::Tie {} => Some(0),
+        GameResult::HostWins {} => Some(1),
+        GameResult::OpponentWins {} => Some(2),
+        _ => None,
+    }
+}
+
+/// MiMC-7 round constants, the first round has none
+pub fn mimc_constants() -> Vec<Fr> {
+    (0..MIMC_ROUNDS)
+        .map(|i| match i {
+            0 => Fr::zero(),
+            _ => Fr::from_le_bytes_mod_order(&sha_256(format!("jaken_mimc_{}", i).as_bytes())),
+        })
+        .collect()
+}
+
+/// Commitment to a host move for ZK games, computed off chain by the host
+pub fn mimc_commit(host_move: &GameMove, salt: Fr) -> Option<Fr> {
+    let m = Fr::from(move_index(host_move)?);
+    let mut x = m;
+    for c in mimc_constants() {
+        let s = x + salt + c;
+        let s2 = s.square();
+        x = s2.square() * s2 * s;
+    }
+    Some(x + salt + m)
+}
+
+pub fn field_to_bytes(value: &Fr) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(FIELD_SIZE);
+    // writing into a vec cannot fail
+    value.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+pub fn field_from_bytes(bytes: &[u8]) -> Result<Fr, ContractError> {
+    if bytes.len() != FIELD_SIZE {
+        return Err(ContractError::InvalidCommitment {});
+    }
+    Fr::deserialize_compressed(bytes).map_err(|_| ContractError::InvalidCommitment {})
+}
+
+pub fn validate_verifying_key(vk: &[u8]) -> Result<(), ContractError> {
+    VerifyingKey::<Bn254>::deserialize_compressed(vk)
+        .map(|_| ())
+        .map_err(|_| ContractError::InvalidVerifyingKey {})
+}
+
+/// Verifies that the committed host move against `opp_move` gives `result`.
+/// Runs in pure Rust, no host crypto API is involved.
+pub fn verify_outcome(
+    vk: &[u8],
+    proof: &[u8],
+    commitment: &[u8],
+    opp_move: &GameMove,
+    result: &GameResult,
+) -> Result<(), ContractError> {
+    let outcome = outcome_index(result).ok_or(ContractError::InvalidProof {})?;
+    let opp_move = move_index(opp_move).ok_or(ContractError::UnsupportedGameKind {})?;
+    let vk = VerifyingKey::<Bn254>::deserialize_compressed(vk)
+        .map_err(|_| ContractError::InvalidVerifyingKey {})?;
+    let proof = Proof::<Bn254>::deserialize_compressed(proof)
+        .map_err(|_| ContractError::InvalidProof {})?;
+
+    let inputs = [
+        field_from_bytes(commitment)?,
+        Fr::from(opp_move),
+        Fr::from(outcome),
+    ];
+    let valid = Groth16::<Bn254>::verify_proof(&prepare_verifying_key(&vk), &proof, &inputs)
+        .map_err(|_| ContractError::InvalidProof {})?;
+    match valid {
+        true => Ok(()),
+        false => Err(ContractError::InvalidProof {}),
+    }
+}
+
+/// The statement proven by the host. Witnesses are only needed to prove,
+/// the trusted setup runs the circuit without them.
+#[derive(Clone, Default)]
+pub struct OutcomeCircuit {
+    pub commitment: Option<Fr>,
+    pub opp_move: Option<u64>,
+    pub outcome: Option<u64>,
+    pub host_move: Option<u64>,
+    pub salt: Option<Fr>,
+}
+
+impl ConstraintSynthesizer<Fr> for OutcomeCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let missing = || SynthesisError::AssignmentMissing;
+        let commitment = cs.new_input_variable(|| self.commitment.ok_or_else(missing))?;
+        let opp = cs.new_input_variable(|| self.opp_move.map(Fr::from).ok_or_else(missing))?;
+        let outcome = cs.new_input_variable(|| self.outcome.map(Fr::from).ok_or_else(missing))?;
+
+        let m_val = self.host_move.map(Fr::from);
+        let m = cs.new_witness_variable(|| m_val.ok_or_else(missing))?;
+        let salt = cs.new_witness_variable(|| self.salt.ok_or_else(missing))?;
+
+        // host move is 0, 1 or 2: m * (m - 1) * (m - 2) = 0
+        let t_val = m_val.map(|m| m * (m - Fr::one()));
+        let t = cs.new_witness_variable(|| t_val.ok_or_else(missing))?;
+        cs.enforce_constraint(lc!() + m, lc!() + m - (Fr::one(), Variable::One), lc!() + t)?;
+        cs.enforce_constraint(
+            lc!() + t,
+            lc!() + m - (Fr::from(2u64), Variable::One),
+            lc!(),
+        )?;
+
+        // commitment = mimc(m, salt) + m + salt
+        let mut x: LinearCombination<Fr> = lc!() + m;
+        let mut x_val = m_val;
+        for c in mimc_constants() {
+            let s: LinearCombination<Fr> = x.clone() + salt + (c, Variable::One);
+            let s_val = x_val.zip(self.salt).map(|(x, k)| x + k + c);
+            let s2_val = s_val.map(|s| s.square());
+            let s4_val = s2_val.map(|s2| s2.square());
+            let s6_val = s4_val.zip(s2_val).map(|(s4, s2)| s4 * s2);
+            let next_val = s6_val.zip(s_val).map(|(s6, s)| s6 * s);
+
+            let s2 = cs.new_witness_variable(|| s2_val.ok_or_else(missing))?;
+            let s4 = cs.new_witness_variable(|| s4_val.ok_or_else(missing))?;
+            let s6 = cs.new_witness_variable(|| s6_val.ok_or_else(missing))?;
+            let next = cs.new_witness_variable(|| next_val.ok_or_else(missing))?;
+            cs.enforce_constraint(s.clone(), s.clone(), lc!() + s2)?;
+            cs.enforce_constraint(lc!() + s2, lc!() + s2, lc!() + s4)?;
+            cs.enforce_constraint(lc!() + s4, lc!() + s2, lc!() + s6)?;
+            cs.enforce_constraint(lc!() + s6, s, lc!() + next)?;
+
+            x = lc!() + next;
+            x_val = next_val;
+        }
+        cs.enforce_constraint(x + m + salt, lc!() + Variable::One, lc!() + commitment)?;
+
+        // outcome = (m - opp) mod 3: with d = m - opp + 3 - outcome, d * (d - 3) = 0
+        let d_minus_3 = lc!() + m - opp - outcome;
+        let d = d_minus_3.clone() + (Fr::from(3u64), Variable::One);
+        cs.enforce_constraint(d, d_minus_3, lc!())?;
+
+        Ok(())
+    }
+}